@@ -0,0 +1,216 @@
+use crate::error;
+use crate::message;
+
+use anyhow::Context;
+
+pub const DEFAULT_BACKUP_COUNT: usize = 5;
+
+// backups use their own extension so the game does not load them from the downloads directory
+const BACKUP_EXTENSION: &str = "bak";
+
+/// The directory that holds the backups of an iff file, e.g. `downloads/backups/chair/` for `downloads/chair.iff`.
+pub fn backup_directory(iff_file_path: &std::path::Path) -> anyhow::Result<std::path::PathBuf> {
+    let parent_directory = iff_file_path.parent().unwrap_or(std::path::Path::new(""));
+    let file_stem = iff_file_path
+        .file_stem()
+        .with_context(|| format!("Failed to get file name of {}", iff_file_path.display()))?;
+    Ok(parent_directory.join("backups").join(file_stem))
+}
+
+fn timestamp() -> String {
+    let seconds = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or(0);
+    let (days, seconds_of_day) = (seconds / 86400, seconds % 86400);
+
+    // convert days since 1970-01-01 to a date in the proleptic gregorian calendar
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    // colons are not allowed in file names on windows
+    format!(
+        "{:04}-{:02}-{:02} {:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60
+    )
+}
+
+// backups made within the same second get a counter after their timestamp, e.g. `2024-01-31 12-00-00-2.bak`
+fn backup_order(backup: &std::path::Path) -> (String, u32) {
+    const TIMESTAMP_LENGTH: usize = "2024-01-31 12-00-00".len();
+    let file_stem = backup.file_stem().unwrap_or_default().to_string_lossy();
+    let (timestamp, counter) = if file_stem.len() > TIMESTAMP_LENGTH && file_stem.is_char_boundary(TIMESTAMP_LENGTH) {
+        file_stem.split_at(TIMESTAMP_LENGTH)
+    } else {
+        (file_stem.as_ref(), "")
+    };
+    (
+        timestamp.to_owned(),
+        counter.trim_start_matches('-').parse().unwrap_or(0),
+    )
+}
+
+/// Lists the backups of an iff file from oldest to newest.
+pub fn list_backups(iff_file_path: &std::path::Path) -> anyhow::Result<Vec<std::path::PathBuf>> {
+    let backup_directory = backup_directory(iff_file_path)?;
+    if !backup_directory.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    let entries = std::fs::read_dir(&backup_directory)
+        .with_context(|| format!("Failed to read directory {}", backup_directory.display()))?;
+    for entry in entries {
+        let path = entry.with_context(|| format!("Failed to read directory {}", backup_directory.display()))?.path();
+        if path.is_file() && path.extension().is_some_and(|x| x == BACKUP_EXTENSION) {
+            backups.push(path);
+        }
+    }
+    // the timestamps sort in chronological order
+    backups.sort_by_cached_key(|x| backup_order(x));
+    Ok(backups)
+}
+
+/// Copies an iff file into its backup directory and removes the oldest backups so that at most `backup_count` remain.
+pub fn create_backup(iff_file_path: &std::path::Path, backup_count: usize) -> anyhow::Result<()> {
+    if backup_count == 0 || !iff_file_path.is_file() {
+        return Ok(());
+    }
+
+    let backup_directory = backup_directory(iff_file_path)?;
+    std::fs::create_dir_all(&backup_directory)
+        .with_context(|| format!("Failed to create directory {}", backup_directory.display()))?;
+
+    let timestamp = timestamp();
+    let mut backup_file_path = backup_directory.join(format!("{timestamp}.{BACKUP_EXTENSION}"));
+    for i in 1.. {
+        if !backup_file_path.exists() {
+            break;
+        }
+        backup_file_path = backup_directory.join(format!("{timestamp}-{i}.{BACKUP_EXTENSION}"));
+    }
+    std::fs::copy(iff_file_path, &backup_file_path).with_context(|| {
+        format!(
+            "Failed to back up {} to {}",
+            iff_file_path.display(),
+            backup_file_path.display()
+        )
+    })?;
+
+    let backups = list_backups(iff_file_path)?;
+    for backup in &backups[..backups.len().saturating_sub(backup_count)] {
+        std::fs::remove_file(backup).with_context(|| format!("Failed to remove old backup {}", backup.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Writes a file by writing a temporary file next to it first and renaming it, so the file is never left half written.
+pub fn write_file_atomically(file_path: &std::path::Path, data: &[u8]) -> anyhow::Result<()> {
+    let mut temporary_file_name = file_path
+        .file_name()
+        .with_context(|| format!("Failed to get file name of {}", file_path.display()))?
+        .to_owned();
+    temporary_file_name.push(".tmp");
+    let temporary_file_path = file_path.with_file_name(temporary_file_name);
+
+    let result = std::fs::write(&temporary_file_path, data)
+        .with_context(|| error::file_write_error(&temporary_file_path))
+        .and_then(|_| {
+            std::fs::rename(&temporary_file_path, file_path).with_context(|| error::file_write_error(file_path))
+        });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temporary_file_path);
+    }
+    result
+}
+
+/// Lists the backups of an iff file, or restores the named backup. `latest` restores the newest backup. The file is
+/// backed up before it is overwritten so a restore can be undone.
+pub fn restore(iff_file_path: &std::path::Path, backup_name: Option<&str>, backup_count: usize) -> anyhow::Result<()> {
+    let backups = list_backups(iff_file_path)?;
+
+    let Some(backup_name) = backup_name else {
+        if backups.is_empty() {
+            message::info(&format!("No backups of {} found", iff_file_path.display()));
+        }
+        for backup in &backups {
+            let size = std::fs::metadata(backup).map(|x| x.len()).unwrap_or(0);
+            message::info(&format!(
+                "{:<24} {:>10} bytes",
+                backup.file_stem().unwrap_or_default().to_string_lossy(),
+                size
+            ));
+        }
+        return Ok(());
+    };
+
+    let backup = if backup_name == "latest" {
+        backups.last()
+    } else {
+        backups.iter().find(|x| x.file_stem().is_some_and(|x| x == backup_name))
+    }
+    .with_context(|| {
+        format!(
+            "Failed to find backup \"{}\" of {}",
+            backup_name,
+            iff_file_path.display()
+        )
+    })?;
+
+    // the backup is read first because backing up the current file may remove it
+    let backup_data = std::fs::read(backup).with_context(|| error::file_read_error(backup))?;
+    create_backup(iff_file_path, backup_count)?;
+    write_file_atomically(iff_file_path, &backup_data)?;
+    message::info(&format!(
+        "Restored {} from {}",
+        iff_file_path.display(),
+        backup.display()
+    ));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backups_of_the_same_second_sort_after_the_first_one() {
+        let mut backups: Vec<_> = [
+            "2024-01-31 12-00-00-10.bak",
+            "2024-01-31 12-00-01.bak",
+            "2024-01-31 12-00-00-2.bak",
+            "2024-01-31 12-00-00.bak",
+            "2024-01-31 12-00-00-1.bak",
+        ]
+        .iter()
+        .map(std::path::PathBuf::from)
+        .collect();
+        backups.sort_by_cached_key(|x| backup_order(x));
+        assert_eq!(
+            backups,
+            [
+                "2024-01-31 12-00-00.bak",
+                "2024-01-31 12-00-00-1.bak",
+                "2024-01-31 12-00-00-2.bak",
+                "2024-01-31 12-00-00-10.bak",
+                "2024-01-31 12-00-01.bak",
+            ]
+            .map(std::path::PathBuf::from)
+        );
+    }
+}
@@ -0,0 +1,614 @@
+use crate::error;
+use crate::iff;
+use crate::message;
+use crate::objd;
+
+use anyhow::Context;
+
+pub const BHAV_OPERAND_SIZE: usize = 8;
+const BHAV_INSTRUCTION_SIZE: usize = 4 + BHAV_OPERAND_SIZE;
+const BHAV_INSTRUCTION_TOKEN_COUNT: usize = 7 + BHAV_OPERAND_SIZE;
+
+const TARGET_ERROR: u8 = 253;
+const TARGET_TRUE: u8 = 254;
+const TARGET_FALSE: u8 = 255;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BehaviourSource {
+    #[serde(rename = "@name")]
+    pub chunk_label: String,
+    #[serde(rename = "@id")]
+    pub chunk_id: iff::IffChunkId,
+    #[serde(rename = "@filename")]
+    pub file_path_relative: String,
+}
+
+impl BehaviourSource {
+    pub fn to_chunk(&self, source_directory: &std::path::Path) -> anyhow::Result<iff::IffChunk> {
+        let listing_file_path = source_directory.join(&self.file_path_relative);
+        let listing =
+            std::fs::read_to_string(&listing_file_path).with_context(|| error::file_read_error(&listing_file_path))?;
+        let behaviour = Behaviour::assemble(&listing).with_context(|| {
+            format!(
+                "Failed to assemble behaviour {} {} from {}",
+                self.chunk_id.as_i16(),
+                self.chunk_label,
+                listing_file_path.display()
+            )
+        })?;
+        behaviour.to_chunk(self.chunk_id, &self.chunk_label)
+    }
+}
+
+pub struct BehaviourInstruction {
+    pub opcode: u16,
+    pub true_target: u8,
+    pub false_target: u8,
+    pub operands: [u8; BHAV_OPERAND_SIZE],
+}
+
+impl BehaviourInstruction {
+    pub fn guid(&self) -> Option<i32> {
+        // set to next, test object type and create new object instance reference objects by GUID
+        if matches!(self.opcode, 31 | 32 | 42) {
+            Some(i32::from_le_bytes(self.operands[..4].try_into().unwrap()))
+        } else {
+            None
+        }
+    }
+}
+
+pub struct Behaviour {
+    pub signature: u16,
+    pub tree_type: u8,
+    pub argument_count: u8,
+    pub local_count: u16,
+    pub tree_version: u16,
+    pub instructions: Vec<BehaviourInstruction>,
+}
+
+impl Behaviour {
+    pub fn from_chunk(chunk: &iff::IffChunk) -> anyhow::Result<Behaviour> {
+        let mut reader = iff::IffChunkReader::new(&chunk.data);
+
+        let signature = reader.read_u16()?;
+        let (instruction_count, tree_type, argument_count, local_count, tree_version) = match signature {
+            0x8000 | 0x8001 => {
+                let instruction_count = u32::from(reader.read_u16()?);
+                let _ = reader.read_bytes(8)?;
+                (instruction_count, 0, 0, 0, 0)
+            }
+            0x8002 => {
+                let instruction_count = u32::from(reader.read_u16()?);
+                let tree_type = reader.read_u8()?;
+                let argument_count = reader.read_u8()?;
+                let local_count = reader.read_u16()?;
+                let tree_version = reader.read_u16()?;
+                let _ = reader.read_u16()?;
+                (instruction_count, tree_type, argument_count, local_count, tree_version)
+            }
+            0x8003 => {
+                let tree_type = reader.read_u8()?;
+                let argument_count = reader.read_u8()?;
+                let local_count = u16::from(reader.read_u8()?);
+                let _ = reader.read_u16()?;
+                let tree_version = reader.read_u16()?;
+                let instruction_count = reader.read_u32()?;
+                (instruction_count, tree_type, argument_count, local_count, tree_version)
+            }
+            signature => anyhow::bail!(
+                "Unknown signature 0x{:04x} in behaviour {} {}",
+                signature,
+                chunk.header.id().as_i16(),
+                chunk.header.label()
+            ),
+        };
+
+        let mut instructions = Vec::new();
+        for _ in 0..instruction_count {
+            instructions.push(BehaviourInstruction {
+                opcode: reader.read_u16()?,
+                true_target: reader.read_u8()?,
+                false_target: reader.read_u8()?,
+                operands: reader.read_bytes(BHAV_OPERAND_SIZE)?.try_into().unwrap(),
+            });
+        }
+
+        Ok(Behaviour {
+            signature,
+            tree_type,
+            argument_count,
+            local_count,
+            tree_version,
+            instructions,
+        })
+    }
+
+    pub fn assemble(listing: &str) -> anyhow::Result<Behaviour> {
+        let mut behaviour = Behaviour {
+            signature: 0x8002,
+            tree_type: 0,
+            argument_count: 0,
+            local_count: 0,
+            tree_version: 0,
+            instructions: Vec::new(),
+        };
+
+        for (line_index, line) in listing.lines().enumerate() {
+            // everything after a semicolon is a comment
+            let line = line.split(';').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            behaviour
+                .assemble_line(line)
+                .with_context(|| format!("Failed to assemble line {}", line_index + 1))?;
+        }
+
+        let instruction_count = behaviour.instructions.len();
+        anyhow::ensure!(instruction_count > 0, "Behaviour has no instructions");
+        anyhow::ensure!(
+            instruction_count <= usize::from(TARGET_ERROR),
+            "Behaviour has more than {} instructions",
+            TARGET_ERROR
+        );
+        for (i, instruction) in behaviour.instructions.iter().enumerate() {
+            for target in [instruction.true_target, instruction.false_target] {
+                anyhow::ensure!(
+                    target >= TARGET_ERROR || usize::from(target) < instruction_count,
+                    "Instruction {} targets instruction {} which does not exist",
+                    i,
+                    target
+                );
+            }
+        }
+
+        Ok(behaviour)
+    }
+
+    fn assemble_line(&mut self, line: &str) -> anyhow::Result<()> {
+        let tokens: Vec<_> = line.split_whitespace().collect();
+
+        if let Some(index) = tokens[0].strip_suffix(':') {
+            let index: usize = parse_number(index)?;
+            anyhow::ensure!(
+                index == self.instructions.len(),
+                "Expected instruction {} but found instruction {}",
+                self.instructions.len(),
+                index
+            );
+            self.instructions.push(parse_instruction(&tokens)?);
+            return Ok(());
+        }
+
+        anyhow::ensure!(tokens.len() == 2, "Expected a header field like \"locals 2\"");
+        anyhow::ensure!(
+            self.instructions.is_empty(),
+            "Header fields must come before the instructions"
+        );
+        match tokens[0] {
+            "signature" => {
+                let signature = parse_number(tokens[1])?;
+                anyhow::ensure!(
+                    (0x8000..=0x8003).contains(&signature),
+                    "Unknown signature 0x{:04x}",
+                    signature
+                );
+                self.signature = signature;
+            }
+            "type" => self.tree_type = parse_number(tokens[1])?,
+            "arguments" => self.argument_count = parse_number(tokens[1])?,
+            "locals" => self.local_count = parse_number(tokens[1])?,
+            "version" => self.tree_version = parse_number(tokens[1])?,
+            field => anyhow::bail!("Unknown header field \"{}\"", field),
+        }
+        Ok(())
+    }
+
+    pub fn to_chunk(&self, chunk_id: iff::IffChunkId, chunk_label: &str) -> anyhow::Result<iff::IffChunk> {
+        let mut bhav_data = Vec::new();
+
+        let instruction_count = u16::try_from(self.instructions.len()).unwrap();
+        bhav_data.extend_from_slice(&self.signature.to_le_bytes());
+        match self.signature {
+            0x8000 | 0x8001 => {
+                bhav_data.extend_from_slice(&instruction_count.to_le_bytes());
+                bhav_data.extend_from_slice(&0u64.to_le_bytes());
+            }
+            0x8002 => {
+                bhav_data.extend_from_slice(&instruction_count.to_le_bytes());
+                bhav_data.push(self.tree_type);
+                bhav_data.push(self.argument_count);
+                bhav_data.extend_from_slice(&self.local_count.to_le_bytes());
+                bhav_data.extend_from_slice(&self.tree_version.to_le_bytes());
+                bhav_data.extend_from_slice(&0u16.to_le_bytes());
+            }
+            0x8003 => {
+                bhav_data.push(self.tree_type);
+                bhav_data.push(self.argument_count);
+                bhav_data.push(
+                    u8::try_from(self.local_count)
+                        .with_context(|| format!("Signature 0x8003 behaviours can have at most {} locals", u8::MAX))?,
+                );
+                bhav_data.extend_from_slice(&0u16.to_le_bytes());
+                bhav_data.extend_from_slice(&self.tree_version.to_le_bytes());
+                bhav_data.extend_from_slice(&u32::from(instruction_count).to_le_bytes());
+            }
+            signature => anyhow::bail!("Unknown signature 0x{:04x}", signature),
+        }
+
+        for instruction in &self.instructions {
+            bhav_data.extend_from_slice(&instruction.opcode.to_le_bytes());
+            bhav_data.push(instruction.true_target);
+            bhav_data.push(instruction.false_target);
+            bhav_data.extend_from_slice(&instruction.operands);
+        }
+
+        Ok(iff::IffChunk {
+            header: iff::IffChunkHeader::new(b"BHAV", bhav_data.len(), chunk_id, chunk_label)?,
+            data: bhav_data,
+        })
+    }
+}
+
+pub fn replace_guids_in_behaviour(chunk: &mut iff::IffChunk, guids: &std::collections::HashMap<i32, i32>) {
+    // behaviours that can not be decoded are left as they are rather than failing the whole rebuild
+    let behaviour = match Behaviour::from_chunk(chunk) {
+        Ok(behaviour) => behaviour,
+        Err(error) => {
+            let chunk_label = chunk.header.label();
+            message::warning(
+                &format!(
+                    "GUIDs in behaviour {} {} were not replaced because it could not be read: {:#}",
+                    chunk.header.id().as_i16(),
+                    chunk_label,
+                    error
+                ),
+                Some(&message::ChunkReference::new("BHAV", chunk.header.id(), &chunk_label)),
+            );
+            return;
+        }
+    };
+
+    // signature 0x8003 has a 13 byte header, all the others have a 12 byte header
+    let header_size = if behaviour.signature == 0x8003 { 13 } else { 12 };
+    const GUID_OFFSET: usize = 4;
+
+    for (i, instruction) in behaviour.instructions.iter().enumerate() {
+        if let Some(new_guid) = instruction.guid().and_then(|x| guids.get(&x)) {
+            let guid_address = header_size + i * BHAV_INSTRUCTION_SIZE + GUID_OFFSET;
+            chunk.data[guid_address..guid_address + 4].copy_from_slice(&new_guid.to_le_bytes());
+        }
+    }
+}
+
+fn parse_instruction(tokens: &[&str]) -> anyhow::Result<BehaviourInstruction> {
+    anyhow::ensure!(
+        tokens.len() == BHAV_INSTRUCTION_TOKEN_COUNT
+            && tokens[2] == "true"
+            && tokens[4] == "false"
+            && tokens[6] == "operands",
+        "Expected an instruction like \"0: 0x0002 true 1 false error operands 00 00 00 00 00 00 00 00\""
+    );
+    let mut operands = [0u8; BHAV_OPERAND_SIZE];
+    for (operand, token) in operands.iter_mut().zip(&tokens[7..]) {
+        *operand = u8::from_str_radix(token, 16).with_context(|| format!("Invalid operand byte \"{token}\""))?;
+    }
+    Ok(BehaviourInstruction {
+        opcode: parse_number(tokens[1])?,
+        true_target: parse_target(tokens[3])?,
+        false_target: parse_target(tokens[5])?,
+        operands,
+    })
+}
+
+fn format_instruction(index: usize, instruction: &BehaviourInstruction) -> String {
+    let operands: Vec<_> = instruction.operands.iter().map(|x| format!("{x:02x}")).collect();
+    format!(
+        "{:>3}: 0x{:04x} true {:<5} false {:<5} operands {}",
+        index,
+        instruction.opcode,
+        target_name(instruction.true_target),
+        target_name(instruction.false_target),
+        operands.join(" ")
+    )
+}
+
+pub fn replace_guids_in_listing(listing: &str, guids: &std::collections::HashMap<i32, i32>) -> anyhow::Result<String> {
+    let mut replaced_listing = String::with_capacity(listing.len());
+    for (line_index, line) in listing.lines().enumerate() {
+        let (code, comment) = match line.split_once(';') {
+            Some((code, comment)) => (code, Some(comment)),
+            None => (line, None),
+        };
+        let tokens: Vec<_> = code.split_whitespace().collect();
+
+        let replacement = match tokens.first().and_then(|x| x.strip_suffix(':')) {
+            Some(index) => {
+                let index: usize = parse_number(index).with_context(|| format!("Invalid line {}", line_index + 1))?;
+                let mut instruction =
+                    parse_instruction(&tokens).with_context(|| format!("Invalid line {}", line_index + 1))?;
+                instruction
+                    .guid()
+                    .and_then(|x| guids.get(&x).map(|new_guid| (x, *new_guid)))
+                    .map(|(guid, new_guid)| {
+                        instruction.operands[..4].copy_from_slice(&new_guid.to_le_bytes());
+                        let comment =
+                            comment.map(|x| x.replace(&format!("0x{guid:08x}"), &format!("0x{new_guid:08x}")));
+                        match comment {
+                            Some(comment) => format!("{} ;{}", format_instruction(index, &instruction), comment),
+                            None => format_instruction(index, &instruction),
+                        }
+                    })
+            }
+            None => None,
+        };
+
+        replaced_listing.push_str(replacement.as_deref().unwrap_or(line));
+        replaced_listing.push('\n');
+    }
+    Ok(replaced_listing)
+}
+
+fn parse_number<T: TryFrom<u64>>(token: &str) -> anyhow::Result<T> {
+    let number = match token.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => token.parse(),
+    }
+    .with_context(|| format!("Invalid number \"{token}\""))?;
+    T::try_from(number).map_err(|_| anyhow::anyhow!("Number {} is out of range", token))
+}
+
+fn parse_target(token: &str) -> anyhow::Result<u8> {
+    match token {
+        "error" => Ok(TARGET_ERROR),
+        "true" => Ok(TARGET_TRUE),
+        "false" => Ok(TARGET_FALSE),
+        token => parse_number(token),
+    }
+}
+
+fn primitive_name(opcode: u16) -> Option<&'static str> {
+    const PRIMITIVE_NAMES: [&str; 51] = [
+        "Sleep",
+        "Generic Sims Call",
+        "Expression",
+        "Find Best Interaction",
+        "Grab",
+        "Drop",
+        "Change Suit/Accessory",
+        "Refresh",
+        "Random Number",
+        "Burn",
+        "Tutorial",
+        "Get Distance To",
+        "Get Direction To",
+        "Push Interaction",
+        "Find Best Object For Function",
+        "Breakpoint",
+        "Find Location For",
+        "Idle For Input",
+        "Remove Object Instance",
+        "Make New Character",
+        "Run Functional Tree",
+        "Show String",
+        "Look Towards",
+        "Play Sound Event",
+        "Old Relationship",
+        "Transfer Funds",
+        "Relationship",
+        "Go To Relative Position",
+        "Run Tree By Name",
+        "Set Motive Change",
+        "Gosub Found Action",
+        "Set To Next",
+        "Test Object Type",
+        "Find 5 Worst Motives",
+        "UI Effect",
+        "Special Effect",
+        "Dialog (Private Strings)",
+        "Test Sim Interacting With",
+        "Dialog (Global Strings)",
+        "Dialog (Semi-Global Strings)",
+        "Online Jobs Call",
+        "Set Balloon/Headline",
+        "Create New Object Instance",
+        "Drop Onto",
+        "Animate Sim",
+        "Go To Routing Slot",
+        "Snap",
+        "Reach",
+        "Stop All Sounds",
+        "Notify Stack Object Out Of Idle",
+        "Add/Change Action String",
+    ];
+    PRIMITIVE_NAMES.get(usize::from(opcode)).copied()
+}
+
+fn target_name(target: u8) -> String {
+    match target {
+        TARGET_ERROR => "error".to_owned(),
+        TARGET_TRUE => "true".to_owned(),
+        TARGET_FALSE => "false".to_owned(),
+        target => target.to_string(),
+    }
+}
+
+fn instruction_comment(
+    instruction: &BehaviourInstruction,
+    behaviour_labels: &std::collections::HashMap<i16, String>,
+    object_definitions: &[objd::ObjectDefinition],
+) -> String {
+    let opcode_description = match instruction.opcode {
+        opcode if opcode < 256 => primitive_name(opcode).unwrap_or("Unknown Primitive").to_owned(),
+        opcode if opcode < 4096 => "Global Behaviour".to_owned(),
+        opcode if opcode < 8192 => match i16::try_from(opcode).ok().and_then(|x| behaviour_labels.get(&x)) {
+            Some(label) => format!("Private Behaviour \"{label}\""),
+            None => "Private Behaviour".to_owned(),
+        },
+        _ => "Semi-Global Behaviour".to_owned(),
+    };
+
+    match instruction.guid() {
+        Some(guid) => {
+            let object = match object_definitions.iter().find(|x| x.guid == guid) {
+                Some(object_definition) => format!(
+                    "OBJD {} {}",
+                    object_definition.chunk_id.as_i16(),
+                    object_definition.chunk_label
+                ),
+                None => "not in this file".to_owned(),
+            };
+            format!("{opcode_description}, guid 0x{guid:08x} ({object})")
+        }
+        None => opcode_description,
+    }
+}
+
+pub fn disassemble_behaviour(
+    chunk: &iff::IffChunk,
+    behaviour: &Behaviour,
+    behaviour_labels: &std::collections::HashMap<i16, String>,
+    object_definitions: &[objd::ObjectDefinition],
+) -> String {
+    let mut listing = String::new();
+    use std::fmt::Write;
+
+    writeln!(
+        listing,
+        "; BHAV {} {}",
+        chunk.header.id().as_i16(),
+        chunk.header.label()
+    )
+    .unwrap();
+    writeln!(listing, "signature 0x{:04x}", behaviour.signature).unwrap();
+    writeln!(listing, "type {}", behaviour.tree_type).unwrap();
+    writeln!(listing, "arguments {}", behaviour.argument_count).unwrap();
+    writeln!(listing, "locals {}", behaviour.local_count).unwrap();
+    writeln!(listing, "version {}", behaviour.tree_version).unwrap();
+
+    for (i, instruction) in behaviour.instructions.iter().enumerate() {
+        writeln!(
+            listing,
+            "{} ; {}",
+            format_instruction(i, instruction),
+            instruction_comment(instruction, behaviour_labels, object_definitions)
+        )
+        .unwrap();
+    }
+
+    listing
+}
+
+#[derive(serde::Serialize)]
+struct DisassembledBehaviour {
+    chunk_id: i16,
+    chunk_label: String,
+    listing: String,
+}
+
+pub fn disassemble(iff_file_path: &std::path::Path, behaviour_id: Option<i16>) -> anyhow::Result<()> {
+    let iff = iff::read_iff_file(iff_file_path)?;
+
+    let mut object_definitions = Vec::new();
+    let mut behaviour_labels = std::collections::HashMap::new();
+    for chunk in &iff.chunks {
+        match chunk.header.chunk_type() {
+            b"OBJD" => object_definitions.push(objd::ObjectDefinition::from_chunk(chunk).with_context(|| {
+                format!(
+                    "Failed to decode object definition {} {}",
+                    chunk.header.id().as_i16(),
+                    chunk.header.label()
+                )
+            })?),
+            b"BHAV" => {
+                behaviour_labels.insert(chunk.header.id().as_i16(), chunk.header.label());
+            }
+            _ => (),
+        }
+    }
+
+    let mut listings = Vec::new();
+    for chunk in &iff.chunks {
+        if chunk.header.chunk_type() != b"BHAV" || behaviour_id.is_some_and(|x| x != chunk.header.id().as_i16()) {
+            continue;
+        }
+        let behaviour = Behaviour::from_chunk(chunk).with_context(|| {
+            format!(
+                "Failed to decode behaviour {} {}",
+                chunk.header.id().as_i16(),
+                chunk.header.label()
+            )
+        })?;
+        listings.push(DisassembledBehaviour {
+            chunk_id: chunk.header.id().as_i16(),
+            chunk_label: chunk.header.label(),
+            listing: disassemble_behaviour(chunk, &behaviour, &behaviour_labels, &object_definitions),
+        });
+    }
+
+    if let Some(behaviour_id) = behaviour_id {
+        anyhow::ensure!(
+            !listings.is_empty(),
+            "Failed to find behaviour {} in {}",
+            behaviour_id,
+            iff_file_path.display()
+        );
+    }
+
+    let text: Vec<_> = listings.iter().map(|x| x.listing.as_str()).collect();
+    // every listing already ends with a new line
+    message::data(text.join("\n").trim_end_matches('\n'), &listings);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listings_survive_a_round_trip() {
+        for signature in [0x8002, 0x8003] {
+            let mut guid_operands = [0; BHAV_OPERAND_SIZE];
+            guid_operands[..4].copy_from_slice(&0x12345678_i32.to_le_bytes());
+            let behaviour = Behaviour {
+                signature,
+                tree_type: 1,
+                argument_count: 2,
+                local_count: 3,
+                tree_version: 4,
+                instructions: vec![
+                    BehaviourInstruction {
+                        opcode: 2,
+                        true_target: 1,
+                        false_target: TARGET_ERROR,
+                        operands: [0, 1, 2, 3, 4, 5, 6, 7],
+                    },
+                    BehaviourInstruction {
+                        opcode: 31,
+                        true_target: TARGET_TRUE,
+                        false_target: TARGET_FALSE,
+                        operands: guid_operands,
+                    },
+                    BehaviourInstruction {
+                        opcode: 4096,
+                        true_target: 0,
+                        false_target: 1,
+                        operands: [0xff; BHAV_OPERAND_SIZE],
+                    },
+                ],
+            };
+            let chunk = behaviour.to_chunk(iff::IffChunkId::new(4096), "init; main").unwrap();
+
+            let decoded_behaviour = Behaviour::from_chunk(&chunk).unwrap();
+            let behaviour_labels = std::collections::HashMap::from([(4096, "init; main".to_owned())]);
+            let listing = disassemble_behaviour(&chunk, &decoded_behaviour, &behaviour_labels, &[]);
+
+            let assembled_chunk =
+                Behaviour::assemble(&listing).unwrap().to_chunk(iff::IffChunkId::new(4096), "init; main").unwrap();
+            assert_eq!(assembled_chunk.data, chunk.data, "{listing}");
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::compiler;
+use crate::error;
+use crate::guid;
+use crate::guid_scanner;
+use crate::iff;
+use crate::message;
+use crate::objd;
+
+use anyhow::Context;
+
+pub fn clone(
+    the_sims_directory: &std::path::Path,
+    source_iff_file_path: &std::path::Path,
+    format_string: &str,
+    creator_name: &str,
+    object_name: &str,
+    variant_name: Option<&str>,
+) -> anyhow::Result<()> {
+    let mut iff = iff::read_iff_file(source_iff_file_path)?;
+
+    let source_guids = iff::map_guids(&iff.chunks)?;
+    anyhow::ensure!(
+        !source_guids.is_empty(),
+        error::Error::MissingGuids {
+            path: source_iff_file_path.to_owned()
+        }
+    );
+
+    let the_sims_downloads_path = the_sims_directory.join("downloads");
+    let output_iff_file_path = compiler::get_formatted_iff_file_path(
+        &the_sims_downloads_path,
+        format_string,
+        creator_name,
+        object_name,
+        variant_name,
+    )?;
+    anyhow::ensure!(
+        !output_iff_file_path.exists(),
+        "{} already exists",
+        output_iff_file_path.display()
+    );
+
+    // a fresh install has no downloads directory until something is installed
+    std::fs::create_dir_all(&the_sims_downloads_path)
+        .with_context(|| format!("Failed to create directory {}", the_sims_downloads_path.display()))?;
+
+    // the new GUIDs must not clash with the source object or anything else that is installed
+    let mut used_guids: std::collections::HashSet<_> =
+        guid_scanner::scan_guid_owners(the_sims_directory, false)?.into_keys().collect();
+    used_guids.extend(source_guids.values());
+
+    let mut clone_guids = std::collections::HashMap::new();
+    for chunk_id in source_guids.keys() {
+        let clone_guid = guid::generate_guid(&used_guids);
+        used_guids.insert(clone_guid);
+        clone_guids.insert(*chunk_id, clone_guid);
+
+        let chunk = iff
+            .chunks
+            .iter_mut()
+            .find(|x| x.header.chunk_type() == b"OBJD" && x.header.id() == *chunk_id)
+            .unwrap();
+        objd::replace_guid_in_chunk(chunk, clone_guid)?;
+    }
+
+    iff::replace_guids_in_bhavs(&mut iff.chunks, &source_guids, &clone_guids);
+
+    iff::write_iff_file(&output_iff_file_path, &iff)?;
+
+    message::info(&format!(
+        "Cloned {} to {}",
+        source_iff_file_path.display(),
+        output_iff_file_path.display()
+    ));
+    for chunk in iff.chunks.iter().filter(|x| x.header.chunk_type() == b"OBJD") {
+        let chunk_id = chunk.header.id();
+        let Some(clone_guid) = clone_guids.get(&chunk_id) else {
+            continue;
+        };
+        message::info(&format!(
+            "OBJD {} GUID 0x{:08x} -> 0x{:08x}",
+            chunk_id.as_i16(),
+            source_guids[&chunk_id],
+            clone_guid
+        ));
+    }
+
+    Ok(())
+}
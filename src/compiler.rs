@@ -1,5 +1,9 @@
+use crate::guid_scanner;
 use crate::iff;
 use crate::iff_description;
+use crate::splitter;
+use crate::sprite;
+use crate::xml_updater;
 
 use anyhow::Context;
 
@@ -22,17 +26,16 @@ fn get_iff_file_name_hash(object_name: &str, variant_name: &str) -> String {
     format!("{abbreviated_file_name}{:X}", hasher.finish() as u32)
 }
 
-fn get_formatted_iff_file_path_and_rename_unhashed_iff_file(
+fn format_iff_file_path(
     the_sims_downloads_path: &std::path::Path,
     format_string: &str,
     creator_name: &str,
     object_name: &str,
-    variant_name: Option<&str>,
+    variant_name: &str,
+    iff_file_hash: &str,
 ) -> anyhow::Result<std::path::PathBuf> {
     use formatx::formatx;
 
-    let variant_name = variant_name.unwrap_or("");
-    let iff_file_hash = get_iff_file_name_hash(object_name, variant_name);
     let iff_file_name = formatx!(
         format_string,
         name = creator_name,
@@ -41,17 +44,84 @@ fn get_formatted_iff_file_path_and_rename_unhashed_iff_file(
         variant = variant_name
     )
     .context("Failed to format iff file name")?;
-    let iff_file_path = the_sims_downloads_path.join(iff_file_name).with_extension("iff");
+    Ok(the_sims_downloads_path.join(iff_file_name).with_extension("iff"))
+}
+
+/// The path in the downloads directory of an object's iff file, see `compile-advanced` for the format string.
+pub fn get_formatted_iff_file_path(
+    the_sims_downloads_path: &std::path::Path,
+    format_string: &str,
+    creator_name: &str,
+    object_name: &str,
+    variant_name: Option<&str>,
+) -> anyhow::Result<std::path::PathBuf> {
+    let variant_name = variant_name.unwrap_or("");
+    let iff_file_hash = get_iff_file_name_hash(object_name, variant_name);
+    format_iff_file_path(
+        the_sims_downloads_path,
+        format_string,
+        creator_name,
+        object_name,
+        variant_name,
+        &iff_file_hash,
+    )
+}
+
+/// The path of an object's iff file like [`get_formatted_iff_file_path`], and the path without the hash that the file
+/// has until it is first compiled.
+pub fn formatted_iff_file_paths(
+    the_sims_downloads_path: &std::path::Path,
+    format_string: &str,
+    creator_name: &str,
+    object_name: &str,
+    variant_name: Option<&str>,
+) -> anyhow::Result<Vec<std::path::PathBuf>> {
+    let iff_file_path = get_formatted_iff_file_path(
+        the_sims_downloads_path,
+        format_string,
+        creator_name,
+        object_name,
+        variant_name,
+    )?;
+    let unhashed_path = format_iff_file_path(
+        the_sims_downloads_path,
+        format_string,
+        creator_name,
+        object_name,
+        variant_name.unwrap_or(""),
+        "",
+    )?;
+    Ok(vec![iff_file_path, unhashed_path])
+}
+
+// returns the path of the iff file and the path it can be read from, which is still the unhashed path in a dry run
+fn get_formatted_iff_file_path_and_rename_unhashed_iff_file(
+    the_sims_downloads_path: &std::path::Path,
+    format_string: &str,
+    creator_name: &str,
+    object_name: &str,
+    variant_name: Option<&str>,
+    dry_run: bool,
+) -> anyhow::Result<(std::path::PathBuf, std::path::PathBuf)> {
+    let iff_file_path = get_formatted_iff_file_path(
+        the_sims_downloads_path,
+        format_string,
+        creator_name,
+        object_name,
+        variant_name,
+    )?;
     if !iff_file_path.is_file() {
-        let unhashed_name = formatx!(
+        let unhashed_path = format_iff_file_path(
+            the_sims_downloads_path,
             format_string,
-            name = creator_name,
-            hash = "",
-            object = object_name,
-            variant = variant_name
-        )
-        .context("Failed to format iff file name")?;
-        let unhashed_path = the_sims_downloads_path.join(unhashed_name).with_extension("iff");
+            creator_name,
+            object_name,
+            variant_name.unwrap_or(""),
+            "",
+        )?;
+        if dry_run {
+            return Ok((iff_file_path, unhashed_path));
+        }
         std::fs::rename(&unhashed_path, &iff_file_path).with_context(|| {
             format!(
                 "Failed to rename {} to {}",
@@ -60,10 +130,24 @@ fn get_formatted_iff_file_path_and_rename_unhashed_iff_file(
             )
         })?;
     }
-    Ok(iff_file_path)
+    Ok((iff_file_path.clone(), iff_file_path))
+}
+
+fn assign_new_guids(
+    iff_description: &mut iff_description::IffDescription,
+    input_iff_file_path: &std::path::Path,
+) -> anyhow::Result<std::collections::HashMap<i32, i32>> {
+    let input_iff = iff::read_iff_file(input_iff_file_path)?;
+    let input_guids = iff::map_guids(&input_iff.chunks)?.into_values().collect();
+    Ok(iff_description.assign_new_guids(&input_guids))
 }
 
-pub fn compile(the_sims_directory: &std::path::Path, xml_file_path: &std::path::Path) -> anyhow::Result<()> {
+/// Compiles the description into the iff file it was decompiled from.
+pub fn compile(
+    the_sims_directory: &std::path::Path,
+    xml_file_path: &std::path::Path,
+    options: iff::RebuildOptions,
+) -> anyhow::Result<()> {
     let iff_description = iff_description::IffDescription::open(xml_file_path)
         .with_context(|| format!("Failed to open xml file {}", xml_file_path.display()))?;
 
@@ -76,22 +160,34 @@ pub fn compile(the_sims_directory: &std::path::Path, xml_file_path: &std::path::
             source_directory.display()
         )
     })?;
-    iff_description.update_sprite_positions(source_directory)?;
+    iff_description.update_sprite_positions(source_directory, &sprite::SplitSprites::default())?;
 
-    let input_iff_file_path = the_sims_directory.join(&iff_description.iff_file_path_relative).with_extension("iff");
+    let input_iff_file_path = iff_description.iff_file_path(the_sims_directory);
 
-    iff::rebuild_iff_file(
-        source_directory,
-        &iff_description,
-        &input_iff_file_path,
-        &input_iff_file_path,
-    )?;
+    let replaced_guids = if options.new_guids {
+        assign_new_guids(&mut iff_description, &input_iff_file_path)?
+    } else {
+        std::collections::HashMap::new()
+    };
+    guid_scanner::check_guids(the_sims_directory, &iff_description, &[&input_iff_file_path])?;
+
+    let split_sprites = sprite::SplitSprites::default();
+    let input = iff::RebuildInput::in_place(source_directory, &input_iff_file_path, &split_sprites);
+    iff::rebuild_iff_file(&iff_description, &input, None, options)?;
+    if options.dry_run {
+        return Ok(());
+    }
+
+    // the behaviour listings are sources too and have to follow the new GUIDs
+    iff_description.replace_guids_in_behaviours(source_directory, &replaced_guids)?;
 
     iff_description
         .save(xml_file_path)
         .with_context(|| format!("Failed to save xml file {}", xml_file_path.display()))
 }
 
+/// Compiles `<object_name>.xml` in the source directory into the iff file named by the format string, optionally as a
+/// colour variant of the object.
 pub fn compile_advanced(
     the_sims_directory: &std::path::Path,
     source_directory: &std::path::Path,
@@ -99,7 +195,15 @@ pub fn compile_advanced(
     creator_name: &str,
     object_name: &str,
     variant_names: Option<(&str, &str)>,
+    options: iff::RebuildOptions,
 ) -> anyhow::Result<()> {
+    // the description only holds the GUIDs of the object itself, so fresh GUIDs for a variant could not be kept and
+    // every compile would give it new ones
+    anyhow::ensure!(
+        !options.new_guids || variant_names.is_none_or(|(original, new)| original == new),
+        "New GUIDs can not be given to a variant, clone the object to create a variant with its own GUIDs"
+    );
+
     let xml_file_path = source_directory.join(object_name).with_extension("xml");
 
     let iff_description = iff_description::IffDescription::open(&xml_file_path)
@@ -110,36 +214,101 @@ pub fn compile_advanced(
     if let Some((variant_original, variant_new)) = variant_names {
         iff_description.update_sprite_variants(variant_original, variant_new)?;
     }
-    iff_description.update_sprite_positions(source_directory)?;
+    iff_description.update_sprite_positions(source_directory, &sprite::SplitSprites::default())?;
 
     let (variant_original, variant_new) = variant_names.unzip();
     let the_sims_downloads_path = the_sims_directory.join("downloads");
-    let input_iff_file_path = get_formatted_iff_file_path_and_rename_unhashed_iff_file(
+    let (_, input_iff_file_path) = get_formatted_iff_file_path_and_rename_unhashed_iff_file(
         &the_sims_downloads_path,
         format_string,
         creator_name,
         object_name,
         variant_original,
+        options.dry_run,
     )?;
-    let output_iff_file_path = get_formatted_iff_file_path_and_rename_unhashed_iff_file(
-        &the_sims_downloads_path,
-        format_string,
-        creator_name,
-        object_name,
-        variant_new,
-    )?;
+    let (output_iff_file_path, current_output_iff_file_path) =
+        get_formatted_iff_file_path_and_rename_unhashed_iff_file(
+            &the_sims_downloads_path,
+            format_string,
+            creator_name,
+            object_name,
+            variant_new,
+            options.dry_run,
+        )?;
 
-    iff::rebuild_iff_file(
-        source_directory,
+    let replaced_guids = if options.new_guids {
+        assign_new_guids(&mut iff_description, &input_iff_file_path)?
+    } else {
+        std::collections::HashMap::new()
+    };
+    guid_scanner::check_guids(
+        the_sims_directory,
         &iff_description,
-        &input_iff_file_path,
-        &output_iff_file_path,
+        &[
+            &input_iff_file_path,
+            &output_iff_file_path,
+            &current_output_iff_file_path,
+        ],
     )?;
 
-    if variant_original == variant_new {
+    let input = iff::RebuildInput {
+        source_directory,
+        input_iff_file_path: &input_iff_file_path,
+        output_iff_file_path: &output_iff_file_path,
+        current_output_iff_file_path: &current_output_iff_file_path,
+        split_sprites: &sprite::SplitSprites::default(),
+    };
+    iff::rebuild_iff_file(&iff_description, &input, variant_new, options)?;
+
+    if variant_original == variant_new && !options.dry_run {
+        iff_description.replace_guids_in_behaviours(source_directory, &replaced_guids)?;
         iff_description
             .save(&xml_file_path)
             .with_context(|| format!("Failed to save xml file {}", xml_file_path.display()))?;
     }
     Ok(())
 }
+
+/// Splits the sprites of an object, updates its description and compiles it in one go, keeping the split sprites in
+/// memory. With `write_intermediate_files` the split sprites and the updated xml file are written like `split` and
+/// `update-xml` would.
+///
+/// Only the object itself is built in place. Colour variants need the file names and GUIDs of `compile-advanced`, so
+/// they are split and compiled with `split`, `update-xml` and `compile-advanced`.
+pub fn build(
+    the_sims_directory: &std::path::Path,
+    source_directory: &std::path::Path,
+    object_name: &str,
+    job_count: usize,
+    write_intermediate_files: bool,
+    options: iff::RebuildOptions,
+) -> anyhow::Result<()> {
+    let split_sprites = splitter::split_sprites(source_directory, object_name, None, job_count)?;
+
+    let xml_file_path = source_directory.join(object_name).with_extension("xml");
+    let mut iff_description = iff_description::IffDescription::open(&xml_file_path)
+        .with_context(|| format!("Failed to open xml file {}", xml_file_path.display()))?;
+    xml_updater::replace_sprites(&mut iff_description, source_directory, &split_sprites)?;
+
+    let mut iff_description = iff_description.validate()?;
+    iff_description.update_sprite_positions(source_directory, &split_sprites)?;
+
+    let iff_file_path = iff_description.iff_file_path(the_sims_directory);
+    let input = iff::RebuildInput::in_place(source_directory, &iff_file_path, &split_sprites);
+    iff::rebuild_iff_file(&iff_description, &input, None, options)?;
+
+    if !write_intermediate_files || options.dry_run {
+        return Ok(());
+    }
+
+    let split_sprites_directory = splitter::split_sprites_directory(source_directory, object_name, None);
+    if split_sprites_directory.is_dir() {
+        std::fs::remove_dir_all(&split_sprites_directory)
+            .with_context(|| format!("Failed to remove {}", split_sprites_directory.display()))?;
+    }
+    split_sprites.write()?;
+
+    iff_description
+        .save(&xml_file_path)
+        .with_context(|| format!("Failed to save xml file {}", xml_file_path.display()))
+}
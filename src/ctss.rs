@@ -0,0 +1,103 @@
+use crate::iff;
+use crate::string_table;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CatalogStrings {
+    #[serde(rename = "@name")]
+    pub chunk_label: String,
+    #[serde(rename = "@id")]
+    pub chunk_id: iff::IffChunkId,
+    #[serde(default, rename = "language")]
+    pub languages: Vec<CatalogStringsLanguage>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CatalogStringsLanguage {
+    #[serde(rename = "@code")]
+    pub language_code: u8,
+    #[serde(rename = "@variant", skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(default, rename = "@description")]
+    pub description: String,
+}
+
+impl CatalogStrings {
+    pub fn from_chunk(chunk: &iff::IffChunk) -> anyhow::Result<CatalogStrings> {
+        let string_table = string_table::StringTable::from_chunk(chunk)?;
+
+        // each language has the catalog name followed by the catalog description
+        let mut languages: Vec<CatalogStringsLanguage> = Vec::new();
+        let mut string_counts = std::collections::HashMap::new();
+        for string in string_table.strings {
+            let string_count = string_counts.entry(string.language_code).or_insert(0);
+            *string_count += 1;
+            match *string_count {
+                1 => languages.push(CatalogStringsLanguage {
+                    language_code: string.language_code,
+                    variant: None,
+                    name: string.value,
+                    description: String::new(),
+                }),
+                2 => {
+                    let language = languages.iter_mut().find(|x| x.language_code == string.language_code).unwrap();
+                    language.description = string.value;
+                }
+                _ => anyhow::bail!(
+                    "Catalog strings {} {} have more than a name and a description for language {}",
+                    chunk.header.id().as_i16(),
+                    chunk.header.label(),
+                    string.language_code
+                ),
+            }
+        }
+
+        Ok(CatalogStrings {
+            chunk_label: chunk.header.label(),
+            chunk_id: chunk.header.id(),
+            languages,
+        })
+    }
+
+    fn variant_languages(&self, variant_name: Option<&str>) -> Vec<&CatalogStringsLanguage> {
+        // entries of a variant replace the entry of the same language without a variant
+        let mut languages: Vec<&CatalogStringsLanguage> = Vec::new();
+        for language in &self.languages {
+            if language.variant.is_some() && language.variant.as_deref() != variant_name {
+                continue;
+            }
+            match languages.iter_mut().find(|x| x.language_code == language.language_code) {
+                Some(selected_language) => {
+                    if language.variant.is_some() {
+                        *selected_language = language;
+                    }
+                }
+                None => languages.push(language),
+            }
+        }
+        languages
+    }
+
+    pub fn to_chunk(&self, variant_name: Option<&str>) -> anyhow::Result<iff::IffChunk> {
+        let mut strings = Vec::new();
+        for language in self.variant_languages(variant_name) {
+            for value in [&language.name, &language.description] {
+                strings.push(string_table::StringTableEntry {
+                    language_code: language.language_code,
+                    value: value.clone(),
+                    comment: String::new(),
+                });
+            }
+        }
+
+        let string_table = string_table::StringTable {
+            chunk_label: self.chunk_label.clone(),
+            chunk_id: self.chunk_id,
+            strings,
+        };
+        string_table.to_chunk(b"CTSS")
+    }
+}
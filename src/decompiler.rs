@@ -0,0 +1,478 @@
+use crate::bhav;
+use crate::ctss;
+use crate::dgrp;
+use crate::error;
+use crate::iff;
+use crate::iff_description;
+use crate::message;
+use crate::objd;
+use crate::palt;
+use crate::slot;
+use crate::spr;
+use crate::sprite;
+use crate::string_table;
+
+use anyhow::Context;
+
+const ROTATIONS: [sprite::Rotation; 4] = [
+    sprite::Rotation::NorthWest,
+    sprite::Rotation::NorthEast,
+    sprite::Rotation::SouthEast,
+    sprite::Rotation::SouthWest,
+];
+const ZOOM_LEVELS: [sprite::ZoomLevel; 3] = [sprite::ZoomLevel::Zero, sprite::ZoomLevel::One, sprite::ZoomLevel::Two];
+
+#[derive(Default)]
+struct SpriteFrameUsage {
+    zoom_level_and_rotation: Option<(sprite::ZoomLevel, sprite::Rotation)>,
+    offset_x: Option<i32>,
+    offset_x_flipped: Option<i32>,
+    offset_y: Option<i32>,
+}
+
+fn map_sprite_frame_usage(
+    draw_groups: &[dgrp::DrawGroup],
+) -> std::collections::HashMap<(iff::IffChunkId, i32), SpriteFrameUsage> {
+    const FLIPPED_SPRITE_FLAG: u32 = 1;
+
+    let mut sprite_frame_usage = std::collections::HashMap::new();
+    for draw_group in draw_groups {
+        for draw_group_item_list in &draw_group.draw_group_item_lists {
+            for draw_group_item in &draw_group_item_list.draw_group_items {
+                let usage: &mut SpriteFrameUsage = sprite_frame_usage
+                    .entry((draw_group_item.sprite_chunk_id, draw_group_item.sprite_index.as_i32()))
+                    .or_default();
+                usage
+                    .zoom_level_and_rotation
+                    .get_or_insert((draw_group_item_list.zoom_level, draw_group_item_list.rotation.0));
+                if draw_group_item.flags & FLIPPED_SPRITE_FLAG == 0 {
+                    usage.offset_x.get_or_insert(draw_group_item.sprite_offset_x);
+                } else {
+                    usage.offset_x_flipped.get_or_insert(draw_group_item.sprite_offset_x);
+                }
+                usage.offset_y.get_or_insert(draw_group_item.sprite_offset_y);
+            }
+        }
+    }
+    sprite_frame_usage
+}
+
+fn default_zoom_level_and_rotation(frame_index: usize, frame_count: usize) -> (sprite::ZoomLevel, sprite::Rotation) {
+    // frames that are not used by any draw group are assumed to be ordered by zoom level then rotation
+    if frame_count.is_multiple_of(ZOOM_LEVELS.len()) {
+        let frames_per_zoom_level = frame_count / ZOOM_LEVELS.len();
+        (
+            ZOOM_LEVELS[frame_index / frames_per_zoom_level],
+            ROTATIONS[(frame_index % frames_per_zoom_level) % ROTATIONS.len()],
+        )
+    } else {
+        (sprite::ZoomLevel::Zero, sprite::Rotation::NorthWest)
+    }
+}
+
+fn sprite_frame_directories(
+    sprite_directory: &std::path::Path,
+    frame_placements: &[(sprite::ZoomLevel, sprite::Rotation)],
+) -> Vec<std::path::PathBuf> {
+    // channel file names only contain the zoom level and rotation
+    // so frames that share them need a directory each
+    let is_unique = frame_placements.iter().enumerate().all(|(i, x)| !frame_placements[..i].contains(x));
+    (0..frame_placements.len())
+        .map(|i| {
+            if is_unique {
+                sprite_directory.to_owned()
+            } else {
+                sprite_directory.join(format!("frame {i}"))
+            }
+        })
+        .collect()
+}
+
+fn frame_placements(
+    chunk_id: iff::IffChunkId,
+    frame_count: usize,
+    sprite_frame_usage: &std::collections::HashMap<(iff::IffChunkId, i32), SpriteFrameUsage>,
+) -> Vec<(sprite::ZoomLevel, sprite::Rotation)> {
+    (0..frame_count)
+        .map(|i| {
+            sprite_frame_usage
+                .get(&(chunk_id, i32::try_from(i).unwrap()))
+                .and_then(|x| x.zoom_level_and_rotation)
+                .unwrap_or_else(|| default_zoom_level_and_rotation(i, frame_count))
+        })
+        .collect()
+}
+
+fn create_directory(directory: &std::path::Path) -> anyhow::Result<()> {
+    if !directory.is_dir() {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("Failed to create directory {}", directory.display()))?;
+    }
+    Ok(())
+}
+
+fn decompile_spr2(
+    source_directory: &std::path::Path,
+    sprites_directory: &std::path::Path,
+    chunk: &iff::IffChunk,
+    palettes: &std::collections::HashMap<iff::IffChunkId, Vec<[u8; 3]>>,
+    sprite_frame_usage: &std::collections::HashMap<(iff::IffChunkId, i32), SpriteFrameUsage>,
+) -> anyhow::Result<spr::Sprite> {
+    let chunk_id = chunk.header.id();
+    let chunk_label = chunk.header.label();
+    let spr2 = spr::decode_spr2_chunk(chunk)?;
+
+    let frame_placements = frame_placements(chunk_id, spr2.frames.len(), sprite_frame_usage);
+    let frame_directories = sprite_frame_directories(
+        &sprites_directory.join(iff::chunk_file_name(chunk_id, &chunk_label)),
+        &frame_placements,
+    );
+
+    let mut sprite_frames = Vec::new();
+    for (frame_index, frame) in spr2.frames.iter().enumerate() {
+        let (zoom_level, rotation) = frame_placements[frame_index];
+        let frame_directory = &frame_directories[frame_index];
+        create_directory(frame_directory)?;
+
+        anyhow::ensure!(
+            frame.bounds_left >= 0 && frame.bounds_top >= 0,
+            "Frame {} of sprite {} {} has negative bounds",
+            frame_index,
+            chunk_id.as_i16(),
+            chunk_label
+        );
+        let bounds_left = u32::try_from(frame.bounds_left).unwrap();
+        let bounds_top = u32::try_from(frame.bounds_top).unwrap();
+        let (sprite_width, sprite_height) = sprite::sprite_dimensions(zoom_level);
+        let sprite_width = std::cmp::max(
+            u32::try_from(sprite_width).unwrap(),
+            bounds_left + u32::from(frame.width),
+        );
+        let sprite_height = std::cmp::max(
+            u32::try_from(sprite_height).unwrap(),
+            bounds_top + u32::from(frame.height),
+        );
+
+        let mut sprite_p = image::GrayImage::from_pixel(
+            sprite_width,
+            sprite_height,
+            image::Luma([frame.transparent_color_index]),
+        );
+        let mut sprite_z = image::GrayImage::from_pixel(sprite_width, sprite_height, image::Luma([255]));
+        let mut sprite_a = image::GrayImage::new(sprite_width, sprite_height);
+        let (frame_p, frame_z, frame_a) = frame.channel_images();
+        image::imageops::replace(&mut sprite_p, &frame_p, i64::from(bounds_left), i64::from(bounds_top));
+        image::imageops::replace(&mut sprite_z, &frame_z, i64::from(bounds_left), i64::from(bounds_top));
+        image::imageops::replace(&mut sprite_a, &frame_a, i64::from(bounds_left), i64::from(bounds_top));
+
+        let palette = palettes.get(&frame.palette_chunk_id).with_context(|| {
+            format!(
+                "Failed to find palette {} used in frame {} of sprite {} {}",
+                frame.palette_chunk_id.as_i16(),
+                frame_index,
+                chunk_id.as_i16(),
+                chunk_label
+            )
+        })?;
+
+        let sprite_p_file_path =
+            sprite::sprite_channel_file_path(frame_directory, zoom_level, rotation, sprite::Channel::Color);
+        let sprite_z_file_path =
+            sprite::sprite_channel_file_path(frame_directory, zoom_level, rotation, sprite::Channel::Depth);
+        let sprite_a_file_path =
+            sprite::sprite_channel_file_path(frame_directory, zoom_level, rotation, sprite::Channel::Alpha);
+
+        sprite::write_bmp_with_palette(&sprite_p_file_path, &sprite_p, palette)?;
+        sprite_z.save(&sprite_z_file_path).with_context(|| error::file_write_error(&sprite_z_file_path))?;
+        sprite_a.save(&sprite_a_file_path).with_context(|| error::file_write_error(&sprite_a_file_path))?;
+
+        // the draw group offsets are kept by writing them into the sprite description
+        // which is used to update the draw groups when compiling
+        let bounds = sprite::SpriteBounds {
+            left: frame.bounds_left,
+            top: frame.bounds_top,
+            right: frame.bounds_left + i16::try_from(frame.width).unwrap(),
+            bottom: frame.bounds_top + i16::try_from(frame.height).unwrap(),
+        };
+        let offsets = sprite::calculate_sprite_offsets(sprite_width, &bounds, zoom_level);
+        let offsets = match sprite_frame_usage.get(&(chunk_id, i32::try_from(frame_index).unwrap())) {
+            Some(usage) => sprite::SpriteOffsets {
+                x: usage.offset_x.unwrap_or(offsets.x),
+                y: usage.offset_y.unwrap_or(offsets.y),
+                x_flipped: usage.offset_x_flipped.unwrap_or(offsets.x_flipped),
+            },
+            None => offsets,
+        };
+        let sprite_image_description = sprite::SpriteImageDescription {
+            width: i16::try_from(sprite_width)?,
+            height: i16::try_from(sprite_height)?,
+            bounds,
+            offsets,
+            palette_id: frame.palette_chunk_id,
+            transparent_color_index: frame.transparent_color_index,
+        };
+        sprite::write_sprite_image_description_file(&sprite_image_description, frame_directory, zoom_level, rotation)?;
+
+        sprite_frames.push(spr::SpriteFrame::new(
+            i32::try_from(frame_index).unwrap(),
+            zoom_level,
+            rotation,
+            &sprite_image_description,
+            sprite_p_file_path.strip_prefix(source_directory).unwrap(),
+            sprite_z_file_path.strip_prefix(source_directory).unwrap(),
+            sprite_a_file_path.strip_prefix(source_directory).unwrap(),
+        ));
+    }
+
+    Ok(spr::Sprite::new(
+        &chunk_label,
+        chunk_id,
+        spr2.palette_chunk_id,
+        sprite_frames,
+    ))
+}
+
+fn decompile_spr1(
+    source_directory: &std::path::Path,
+    sprites_directory: &std::path::Path,
+    chunk: &iff::IffChunk,
+    palettes: &std::collections::HashMap<iff::IffChunkId, Vec<[u8; 3]>>,
+    sprite_frame_usage: &std::collections::HashMap<(iff::IffChunkId, i32), SpriteFrameUsage>,
+    object_definitions: &[objd::ObjectDefinition],
+) -> anyhow::Result<spr::Sprite> {
+    let chunk_id = chunk.header.id();
+    let chunk_label = chunk.header.label();
+    let spr1 = spr::decode_spr1_chunk(chunk)?;
+
+    let is_custom_wall_style = object_definitions.iter().any(|x| x.custom_wall_style_sprite_id() == Some(chunk_id));
+
+    let grayscale_palette = palt::grayscale_palette();
+    let palette = if is_custom_wall_style {
+        &grayscale_palette
+    } else {
+        palettes.get(&spr1.palette_chunk_id).unwrap_or(&grayscale_palette)
+    };
+
+    let frame_placements = frame_placements(chunk_id, spr1.frames.len(), sprite_frame_usage);
+    let frame_directories = sprite_frame_directories(
+        &sprites_directory.join(iff::chunk_file_name(chunk_id, &chunk_label)),
+        &frame_placements,
+    );
+
+    let mut sprite_frames = Vec::new();
+    for (frame_index, frame) in spr1.frames.iter().enumerate() {
+        let (zoom_level, rotation) = frame_placements[frame_index];
+        let frame_directory = &frame_directories[frame_index];
+        create_directory(frame_directory)?;
+
+        let (pixels, transparent_color_index) = if is_custom_wall_style {
+            // the transmogrifier exports these sprites with pixels of either 128 or 255
+            const WALL_STYLE_TRANSPARENT_COLOR_INDEX: u8 = 255;
+            const WALL_STYLE_OPAQUE_COLOR_INDEX: u8 = 128;
+            let pixels = frame
+                .pixels
+                .iter()
+                .map(|x| {
+                    if *x == frame.transparent_color_index {
+                        WALL_STYLE_TRANSPARENT_COLOR_INDEX
+                    } else {
+                        WALL_STYLE_OPAQUE_COLOR_INDEX
+                    }
+                })
+                .collect();
+            (pixels, WALL_STYLE_TRANSPARENT_COLOR_INDEX)
+        } else {
+            (frame.pixels.clone(), frame.transparent_color_index)
+        };
+
+        let sprite_z = if frame.width == 0 || frame.height == 0 {
+            image::GrayImage::from_pixel(1, 1, image::Luma([transparent_color_index]))
+        } else {
+            image::GrayImage::from_raw(u32::from(frame.width), u32::from(frame.height), pixels).unwrap()
+        };
+
+        let sprite_z_file_path =
+            sprite::sprite_channel_file_path(frame_directory, zoom_level, rotation, sprite::Channel::Depth);
+        sprite::write_bmp_with_palette(&sprite_z_file_path, &sprite_z, palette)?;
+
+        sprite_frames.push(spr::SpriteFrame::new_spr1(
+            i32::try_from(frame_index).unwrap(),
+            zoom_level,
+            rotation,
+            i16::try_from(sprite_z.width())?,
+            i16::try_from(sprite_z.height())?,
+            spr1.palette_chunk_id,
+            transparent_color_index,
+            sprite_z_file_path.strip_prefix(source_directory).unwrap(),
+        ));
+    }
+
+    Ok(spr::Sprite::new_spr1(
+        &chunk_label,
+        chunk_id,
+        spr1.palette_chunk_id,
+        is_custom_wall_style,
+        sprite_frames,
+    ))
+}
+
+fn decompile_behaviours(
+    source_directory: &std::path::Path,
+    behaviours_directory: &std::path::Path,
+    iff: &iff::Iff,
+    object_definitions: &[objd::ObjectDefinition],
+) -> anyhow::Result<Vec<bhav::BehaviourSource>> {
+    let behaviour_chunks: Vec<_> = iff.chunks.iter().filter(|x| x.header.chunk_type() == b"BHAV").collect();
+    let behaviour_labels: std::collections::HashMap<_, _> =
+        behaviour_chunks.iter().map(|x| (x.header.id().as_i16(), x.header.label())).collect();
+
+    let mut behaviours = Vec::new();
+    for chunk in behaviour_chunks {
+        let behaviour = bhav::Behaviour::from_chunk(chunk).with_context(|| {
+            format!(
+                "Failed to decode behaviour {} {}",
+                chunk.header.id().as_i16(),
+                chunk.header.label()
+            )
+        })?;
+        let listing = bhav::disassemble_behaviour(chunk, &behaviour, &behaviour_labels, object_definitions);
+
+        create_directory(behaviours_directory)?;
+        // appended rather than set as the extension since labels can contain dots
+        let listing_file_name = format!("{}.txt", iff::chunk_file_name(chunk.header.id(), &chunk.header.label()));
+        let listing_file_path = behaviours_directory.join(listing_file_name);
+        std::fs::write(&listing_file_path, listing).with_context(|| error::file_write_error(&listing_file_path))?;
+
+        behaviours.push(bhav::BehaviourSource {
+            chunk_label: chunk.header.label(),
+            chunk_id: chunk.header.id(),
+            file_path_relative: listing_file_path.strip_prefix(source_directory).unwrap().to_str().unwrap().to_owned(),
+        });
+    }
+
+    Ok(behaviours)
+}
+
+/// Writes the description, sprites and behaviour listings of an iff file next to the xml file.
+pub fn decompile(
+    the_sims_directory: &std::path::Path,
+    iff_file_path: &std::path::Path,
+    xml_file_path: &std::path::Path,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(!xml_file_path.exists(), "{} already exists", xml_file_path.display());
+
+    let iff_file_path_relative = iff_file_path
+        .strip_prefix(the_sims_directory)
+        .with_context(|| {
+            format!(
+                "{} is not in The Sims directory {}",
+                iff_file_path.display(),
+                the_sims_directory.display()
+            )
+        })?
+        .with_extension("");
+    let iff_file_path_relative = iff_file_path_relative
+        .to_str()
+        .with_context(|| format!("Failed to convert {} to a string", iff_file_path_relative.display()))?;
+
+    let source_directory = xml_file_path.parent().with_context(|| {
+        format!(
+            "Failed to get source directory from xml file path {}",
+            xml_file_path.display()
+        )
+    })?;
+    let object_name = xml_file_path
+        .file_stem()
+        .with_context(|| {
+            format!(
+                "Failed to get object name from xml file path {}",
+                xml_file_path.display()
+            )
+        })?
+        .to_string_lossy();
+    let sprites_directory = source_directory.join(format!("{} - sprites", object_name));
+    let behaviours_directory = source_directory.join(format!("{} - behaviours", object_name));
+
+    let iff = iff::read_iff_file(iff_file_path)?;
+
+    let mut object_definitions = Vec::new();
+    let mut slots = Vec::new();
+    let mut draw_groups = Vec::new();
+    let mut string_tables = Vec::new();
+    let mut catalog_strings = Vec::new();
+    for chunk in &iff.chunks {
+        let chunk_description = || {
+            format!(
+                "Failed to decode {} chunk {} {}",
+                String::from_utf8_lossy(chunk.header.chunk_type()),
+                chunk.header.id().as_i16(),
+                chunk.header.label()
+            )
+        };
+        match chunk.header.chunk_type() {
+            b"OBJD" => {
+                object_definitions.push(objd::ObjectDefinition::from_chunk(chunk).with_context(chunk_description)?)
+            }
+            b"SLOT" => slots.push(slot::Slot::from_chunk(chunk).with_context(chunk_description)?),
+            b"DGRP" => draw_groups.push(dgrp::DrawGroup::from_chunk(chunk).with_context(chunk_description)?),
+            b"STR#" => {
+                string_tables.push(string_table::StringTable::from_chunk(chunk).with_context(chunk_description)?)
+            }
+            // catalog strings that do not fit the model are left in the iff file untouched
+            b"CTSS" => match ctss::CatalogStrings::from_chunk(chunk) {
+                Ok(x) => catalog_strings.push(x),
+                Err(error) => message::warning(
+                    &format!("{error:#}, they will not be added to the xml file"),
+                    Some(&message::ChunkReference::new(
+                        "CTSS",
+                        chunk.header.id(),
+                        &chunk.header.label(),
+                    )),
+                ),
+            },
+            _ => (),
+        }
+    }
+    anyhow::ensure!(
+        !object_definitions.is_empty(),
+        "Failed to find any object definitions in {}",
+        iff_file_path.display()
+    );
+
+    let palettes = palt::read_palt_chunks(&iff)?;
+    let sprite_frame_usage = map_sprite_frame_usage(&draw_groups);
+
+    let mut sprites = Vec::new();
+    for chunk in &iff.chunks {
+        let sprite = match chunk.header.chunk_type() {
+            b"SPR2" => decompile_spr2(
+                source_directory,
+                &sprites_directory,
+                chunk,
+                &palettes,
+                &sprite_frame_usage,
+            )?,
+            b"SPR#" => decompile_spr1(
+                source_directory,
+                &sprites_directory,
+                chunk,
+                &palettes,
+                &sprite_frame_usage,
+                &object_definitions,
+            )?,
+            _ => continue,
+        };
+        sprites.push(sprite);
+    }
+
+    let behaviours = decompile_behaviours(source_directory, &behaviours_directory, &iff, &object_definitions)?;
+
+    let mut iff_description =
+        iff_description::IffDescription::new(iff_file_path_relative, object_definitions, slots, draw_groups, sprites);
+    iff_description.behaviours.behaviours = behaviours;
+    iff_description.string_tables.string_tables = string_tables;
+    iff_description.catalog.catalog_strings = catalog_strings;
+    iff_description
+        .save(xml_file_path)
+        .with_context(|| format!("Failed to save xml file {}", xml_file_path.display()))
+}
@@ -1,7 +1,10 @@
+use crate::error;
 use crate::iff;
 use crate::spr;
 use crate::sprite;
 
+use anyhow::Context;
+
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct DrawGroup {
@@ -9,7 +12,7 @@ pub struct DrawGroup {
     pub chunk_label: String,
     #[serde(rename = "@id")]
     pub chunk_id: iff::IffChunkId,
-    #[serde(rename = "drawgroupitemlist")]
+    #[serde(default, rename = "drawgroupitemlist")]
     pub draw_group_item_lists: Vec<DrawGroupItemList>,
 }
 
@@ -28,7 +31,7 @@ pub struct DrawGroupItemList {
         rename = "@zoom"
     )]
     pub zoom_level: sprite::ZoomLevel,
-    #[serde(rename = "drawgroupitem")]
+    #[serde(default, rename = "drawgroupitem")]
     pub draw_group_items: Vec<DrawGroupItem>,
 }
 
@@ -54,6 +57,113 @@ pub struct DrawGroupItem {
 }
 
 impl DrawGroup {
+    pub fn from_chunk(chunk: &iff::IffChunk) -> anyhow::Result<DrawGroup> {
+        let mut reader = iff::IffChunkReader::new(&chunk.data);
+
+        let version = reader.read_u16()?;
+        anyhow::ensure!(
+            (20000..=20004).contains(&version),
+            error::Error::UnsupportedVersion {
+                chunk_type: *chunk.header.chunk_type(),
+                chunk_id: chunk.header.id(),
+                chunk_label: chunk.header.label(),
+                version: u32::from(version),
+            }
+        );
+        let wide_fields = version >= 20003;
+        let read_u32 = |reader: &mut iff::IffChunkReader| -> anyhow::Result<u32> {
+            if wide_fields {
+                reader.read_u32()
+            } else {
+                Ok(u32::from(reader.read_u16()?))
+            }
+        };
+
+        let draw_group_item_list_count = read_u32(&mut reader)?;
+        let mut draw_group_item_lists = Vec::new();
+        for _ in 0..draw_group_item_list_count {
+            let rotation_flags = read_u32(&mut reader)?;
+            let rotation = draw_group_rotation(rotation_flags).with_context(|| {
+                format!(
+                    "Unknown rotation {} in draw group {} {}",
+                    rotation_flags,
+                    chunk.header.id().as_i16(),
+                    chunk.header.label()
+                )
+            })?;
+            let zoom_level = match read_u32(&mut reader)? {
+                1 => sprite::ZoomLevel::Zero,
+                2 => sprite::ZoomLevel::One,
+                3 => sprite::ZoomLevel::Two,
+                zoom_level => anyhow::bail!(
+                    "Unknown zoom level {} in draw group {} {}",
+                    zoom_level,
+                    chunk.header.id().as_i16(),
+                    chunk.header.label()
+                ),
+            };
+            let draw_group_item_count = read_u32(&mut reader)?;
+
+            let mut draw_group_items = Vec::new();
+            for _ in 0..draw_group_item_count {
+                let draw_group_item = if wide_fields {
+                    let sprite_chunk_id = reader.read_i32()?;
+                    let sprite_index = reader.read_i32()?;
+                    let sprite_offset_x = reader.read_i32()?;
+                    let sprite_offset_y = reader.read_i32()?;
+                    let object_offset_z = reader.read_f32()?;
+                    let flags = reader.read_u32()?;
+                    let (object_offset_x, object_offset_y) = if version >= 20004 {
+                        (reader.read_f32()?, reader.read_f32()?)
+                    } else {
+                        (0.0, 0.0)
+                    };
+                    DrawGroupItem {
+                        sprite_chunk_id: iff::IffChunkId::new(i16::try_from(sprite_chunk_id)?),
+                        sprite_index: spr::SpriteIndex::new(sprite_index),
+                        sprite_offset_x,
+                        sprite_offset_y,
+                        object_offset_x,
+                        object_offset_y,
+                        object_offset_z,
+                        flags,
+                    }
+                } else {
+                    let _ = reader.read_u16()?;
+                    let sprite_chunk_id = reader.read_i16()?;
+                    let sprite_index = reader.read_u16()?;
+                    let flags = reader.read_u16()?;
+                    let sprite_offset_x = reader.read_i16()?;
+                    let sprite_offset_y = reader.read_i16()?;
+                    let object_offset_z = if version >= 20001 { reader.read_f32()? } else { 0.0 };
+                    DrawGroupItem {
+                        sprite_chunk_id: iff::IffChunkId::new(sprite_chunk_id),
+                        sprite_index: spr::SpriteIndex::new(i32::from(sprite_index)),
+                        sprite_offset_x: i32::from(sprite_offset_x),
+                        sprite_offset_y: i32::from(sprite_offset_y),
+                        object_offset_x: 0.0,
+                        object_offset_y: 0.0,
+                        object_offset_z,
+                        flags: u32::from(flags),
+                    }
+                };
+                draw_group_items.push(draw_group_item);
+            }
+
+            draw_group_item_lists.push(DrawGroupItemList {
+                rotation: (rotation, rotation_flags),
+                zoom_level,
+                draw_group_items,
+            });
+        }
+
+        Ok(DrawGroup {
+            chunk_label: chunk.header.label(),
+            chunk_id: chunk.header.id(),
+            draw_group_item_lists,
+        })
+    }
+
     pub fn to_chunk(&self) -> anyhow::Result<iff::IffChunk> {
         const DGRP_HEADER_VERSION: u16 = 20004u16;
 
@@ -103,12 +213,19 @@ where
     let rotation = u8::deserialize(deserializer)?;
 
     const FIELDS: &[&str] = &["1", "2", "4", "8", "16", "32", "64", "128"];
-    match rotation {
-        1 | 2 => Ok((sprite::Rotation::SouthEast, rotation.into())),
-        4 | 8 => Ok((sprite::Rotation::NorthEast, rotation.into())),
-        16 | 32 => Ok((sprite::Rotation::NorthWest, rotation.into())),
-        64 | 128 => Ok((sprite::Rotation::SouthWest, rotation.into())),
-        _ => Err(serde::de::Error::unknown_field(&rotation.to_string(), FIELDS)),
+    match draw_group_rotation(rotation.into()) {
+        Some(draw_group_rotation) => Ok((draw_group_rotation, rotation.into())),
+        None => Err(serde::de::Error::unknown_field(&rotation.to_string(), FIELDS)),
+    }
+}
+
+fn draw_group_rotation(rotation_flags: u32) -> Option<sprite::Rotation> {
+    match rotation_flags {
+        1 | 2 => Some(sprite::Rotation::SouthEast),
+        4 | 8 => Some(sprite::Rotation::NorthEast),
+        16 | 32 => Some(sprite::Rotation::NorthWest),
+        64 | 128 => Some(sprite::Rotation::SouthWest),
+        _ => None,
     }
 }
 
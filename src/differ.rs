@@ -0,0 +1,254 @@
+use ts1_compiler::dgrp;
+use ts1_compiler::iff;
+use ts1_compiler::message;
+use ts1_compiler::objd;
+use ts1_compiler::slot;
+use ts1_compiler::spr;
+use ts1_compiler::string_table;
+
+#[derive(serde::Serialize)]
+struct SpriteFrameSummary {
+    width: u16,
+    height: u16,
+    bounds_left: i16,
+    bounds_top: i16,
+    palette_id: i16,
+    transparent_color_index: u8,
+    pixel_checksum: String,
+}
+
+#[derive(serde::Serialize)]
+struct SpriteSummary {
+    palette_id: i16,
+    frame: Vec<SpriteFrameSummary>,
+}
+
+fn pixel_checksum(planes: &[&[u8]]) -> String {
+    use std::hash::Hash;
+    use std::hash::Hasher;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    planes.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+fn summarize_spr1_chunk(chunk: &iff::IffChunk) -> anyhow::Result<SpriteSummary> {
+    let spr1 = spr::decode_spr1_chunk(chunk)?;
+    Ok(SpriteSummary {
+        palette_id: spr1.palette_chunk_id.as_i16(),
+        frame: spr1
+            .frames
+            .iter()
+            .map(|x| SpriteFrameSummary {
+                width: x.width,
+                height: x.height,
+                bounds_left: 0,
+                bounds_top: 0,
+                palette_id: spr1.palette_chunk_id.as_i16(),
+                transparent_color_index: x.transparent_color_index,
+                pixel_checksum: pixel_checksum(&[&x.pixels]),
+            })
+            .collect(),
+    })
+}
+
+fn summarize_spr2_chunk(chunk: &iff::IffChunk) -> anyhow::Result<SpriteSummary> {
+    let spr2 = spr::decode_spr2_chunk(chunk)?;
+    Ok(SpriteSummary {
+        palette_id: spr2.palette_chunk_id.as_i16(),
+        frame: spr2
+            .frames
+            .iter()
+            .map(|x| SpriteFrameSummary {
+                width: x.width,
+                height: x.height,
+                bounds_left: x.bounds_left,
+                bounds_top: x.bounds_top,
+                palette_id: x.palette_chunk_id.as_i16(),
+                transparent_color_index: x.transparent_color_index,
+                pixel_checksum: pixel_checksum(&[&x.pixels_p, &x.pixels_z, &x.pixels_a]),
+            })
+            .collect(),
+    })
+}
+
+fn decode_chunk(chunk: &iff::IffChunk) -> anyhow::Result<Option<serde_json::Value>> {
+    let value = match chunk.header.chunk_type() {
+        b"OBJD" => serde_json::to_value(objd::ObjectDefinition::from_chunk(chunk)?)?,
+        b"SLOT" => serde_json::to_value(slot::Slot::from_chunk(chunk)?)?,
+        b"DGRP" => serde_json::to_value(dgrp::DrawGroup::from_chunk(chunk)?)?,
+        b"SPR#" => serde_json::to_value(summarize_spr1_chunk(chunk)?)?,
+        b"SPR2" => serde_json::to_value(summarize_spr2_chunk(chunk)?)?,
+        b"STR#" | b"CTSS" => serde_json::to_value(string_table::StringTable::from_chunk(chunk)?)?,
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
+fn value_path(path: &str, key: &str) -> String {
+    // xml attributes are serialized with an @ prefix
+    let key = key.trim_start_matches('@');
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn diff_values(path: &str, old: &serde_json::Value, new: &serde_json::Value, differences: &mut Vec<String>) {
+    match (old, new) {
+        (serde_json::Value::Object(old), serde_json::Value::Object(new)) => {
+            for (key, old_value) in old {
+                match new.get(key) {
+                    Some(new_value) => diff_values(&value_path(path, key), old_value, new_value, differences),
+                    None => differences.push(format!("{}: removed", value_path(path, key))),
+                }
+            }
+            for key in new.keys().filter(|x| !old.contains_key(*x)) {
+                differences.push(format!("{}: added", value_path(path, key)));
+            }
+        }
+        (serde_json::Value::Array(old), serde_json::Value::Array(new)) => {
+            for i in 0..std::cmp::max(old.len(), new.len()) {
+                let element_path = format!("{path}[{i}]");
+                match (old.get(i), new.get(i)) {
+                    (Some(old_value), Some(new_value)) => diff_values(&element_path, old_value, new_value, differences),
+                    (Some(_), None) => differences.push(format!("{element_path}: removed")),
+                    (None, Some(_)) => differences.push(format!("{element_path}: added")),
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+        (old, new) => {
+            if old != new {
+                differences.push(format!("{path}: {old} -> {new}"));
+            }
+        }
+    }
+}
+
+fn diff_chunks(old: &iff::IffChunk, new: &iff::IffChunk) -> Vec<String> {
+    let mut differences = Vec::new();
+
+    if old.header.label() != new.header.label() {
+        differences.push(format!(
+            "label: \"{}\" -> \"{}\"",
+            old.header.label(),
+            new.header.label()
+        ));
+    }
+    if old.header.flags() != new.header.flags() {
+        differences.push(format!(
+            "flags: 0x{:04x} -> 0x{:04x}",
+            old.header.flags(),
+            new.header.flags()
+        ));
+    }
+
+    match (decode_chunk(old), decode_chunk(new)) {
+        (Ok(Some(mut old_value)), Ok(Some(mut new_value))) => {
+            // the label is already compared above
+            for value in [&mut old_value, &mut new_value] {
+                if let Some(value) = value.as_object_mut() {
+                    value.remove("@name");
+                }
+            }
+            diff_values("", &old_value, &new_value, &mut differences);
+        }
+        (old_value, new_value) => {
+            for error in [old_value.err(), new_value.err()].into_iter().flatten() {
+                differences.push(format!("failed to decode: {error:#}"));
+            }
+            if old.data != new.data {
+                differences.push(format!("data: {} bytes -> {} bytes", old.data.len(), new.data.len()));
+            }
+        }
+    }
+
+    differences
+}
+
+// chunks that share a type and ID are paired with the chunk at the same position among them in the other file
+fn chunk_map(iff: &iff::Iff) -> std::collections::BTreeMap<([u8; 4], i16, usize), &iff::IffChunk> {
+    let mut chunks = std::collections::BTreeMap::new();
+    let mut chunk_counts = std::collections::HashMap::new();
+    for chunk in &iff.chunks {
+        let key = (*chunk.header.chunk_type(), chunk.header.id().as_i16());
+        let chunk_count = chunk_counts.entry(key).or_insert(0);
+        chunks.insert((key.0, key.1, *chunk_count), chunk);
+        *chunk_count += 1;
+    }
+    chunks
+}
+
+fn chunk_name(chunk: &iff::IffChunk, duplicate_index: usize) -> String {
+    let name = format!(
+        "{} {} {}",
+        String::from_utf8_lossy(chunk.header.chunk_type()),
+        chunk.header.id().as_i16(),
+        chunk.header.label()
+    );
+    if duplicate_index == 0 {
+        name
+    } else {
+        format!("{name} (duplicate {duplicate_index})")
+    }
+}
+
+#[derive(serde::Serialize)]
+struct ChunkDifference {
+    change: &'static str,
+    chunk: String,
+    differences: Vec<String>,
+}
+
+pub fn diff(old_iff_file_path: &std::path::Path, new_iff_file_path: &std::path::Path) -> anyhow::Result<()> {
+    let old_iff = iff::read_iff_file(old_iff_file_path)?;
+    let new_iff = iff::read_iff_file(new_iff_file_path)?;
+
+    let old_chunks = chunk_map(&old_iff);
+    let new_chunks = chunk_map(&new_iff);
+
+    let mut chunk_differences = Vec::new();
+    for (key, old_chunk) in &old_chunks {
+        match new_chunks.get(key) {
+            Some(new_chunk) => {
+                let differences = diff_chunks(old_chunk, new_chunk);
+                if !differences.is_empty() {
+                    chunk_differences.push(ChunkDifference {
+                        change: "changed",
+                        chunk: chunk_name(old_chunk, key.2),
+                        differences,
+                    });
+                }
+            }
+            None => chunk_differences.push(ChunkDifference {
+                change: "removed",
+                chunk: chunk_name(old_chunk, key.2),
+                differences: Vec::new(),
+            }),
+        }
+    }
+    for (key, new_chunk) in &new_chunks {
+        if !old_chunks.contains_key(key) {
+            chunk_differences.push(ChunkDifference {
+                change: "added",
+                chunk: chunk_name(new_chunk, key.2),
+                differences: Vec::new(),
+            });
+        }
+    }
+
+    let mut text = Vec::new();
+    for chunk_difference in &chunk_differences {
+        text.push(format!("{:<7} {}", chunk_difference.change, chunk_difference.chunk));
+        for difference in &chunk_difference.differences {
+            text.push(format!("    {difference}"));
+        }
+    }
+    if chunk_differences.is_empty() {
+        text.push("No differences found".to_owned());
+    }
+    message::data(&text.join("\n"), &chunk_differences);
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use crate::iff_description;
+use crate::message;
 use crate::spr;
 
 use anyhow::Context;
@@ -11,10 +12,17 @@ pub fn add_rotations(xml_file_path: &std::path::Path) -> anyhow::Result<()> {
 
     for draw_group in iff_description.draw_groups.draw_groups.iter_mut() {
         if draw_group.draw_group_item_lists.len() != 12 {
-            println!(
-                "Could not convert draw group {} {} which does not have 12 draw group item lists",
-                draw_group.chunk_id.as_i16(),
-                draw_group.chunk_label
+            message::warning(
+                &format!(
+                    "Could not convert draw group {} {} which does not have 12 draw group item lists",
+                    draw_group.chunk_id.as_i16(),
+                    draw_group.chunk_label
+                ),
+                Some(&message::ChunkReference::new(
+                    "DGRP",
+                    draw_group.chunk_id,
+                    &draw_group.chunk_label,
+                )),
             );
             continue;
         }
@@ -80,11 +88,18 @@ pub fn add_rotations(xml_file_path: &std::path::Path) -> anyhow::Result<()> {
                         }
                     }
                 } else if flipped_sprite_id_count > 0 {
-                    println!(
-                        "Flipped sprite detected in draw group {} {}, but could not convert to 4 rotations due to \
-                        unequal flipped sprite usage",
-                        draw_group.chunk_id.as_i16(),
-                        draw_group.chunk_label
+                    message::warning(
+                        &format!(
+                            "Flipped sprite detected in draw group {} {}, but could not convert to 4 rotations due \
+                            to unequal flipped sprite usage",
+                            draw_group.chunk_id.as_i16(),
+                            draw_group.chunk_label
+                        ),
+                        Some(&message::ChunkReference::new(
+                            "DGRP",
+                            draw_group.chunk_id,
+                            &draw_group.chunk_label,
+                        )),
                     );
                     continue;
                 }
@@ -93,11 +108,18 @@ pub fn add_rotations(xml_file_path: &std::path::Path) -> anyhow::Result<()> {
             'outer: for item_list in &draw_group.draw_group_item_lists {
                 for item in &item_list.draw_group_items {
                     if item.flags & FLIPPED_SPRITE_FLAG != 0 {
-                        println!(
-                            "Flipped sprite detected in draw group {} {}, but could not convert to 4 rotations due to \
-                            unequal item list length",
-                            draw_group.chunk_id.as_i16(),
-                            draw_group.chunk_label
+                        message::warning(
+                            &format!(
+                                "Flipped sprite detected in draw group {} {}, but could not convert to 4 rotations \
+                                due to unequal item list length",
+                                draw_group.chunk_id.as_i16(),
+                                draw_group.chunk_label
+                            ),
+                            Some(&message::ChunkReference::new(
+                                "DGRP",
+                                draw_group.chunk_id,
+                                &draw_group.chunk_label,
+                            )),
                         );
                         break 'outer;
                     }
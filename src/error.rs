@@ -1,7 +1,314 @@
-pub fn file_read_error(file_path: &std::path::Path) -> String {
-    format!("Failed to read {}", file_path.display())
+use crate::iff;
+use crate::spr;
+
+/// The failures callers can tell apart, e.g. with `error.downcast_ref::<ts1_compiler::error::Error>()` on the
+/// `anyhow::Error` returned by the library. The messages stay the same as the plain text errors they replace.
+#[derive(Debug)]
+pub enum Error {
+    FileRead {
+        path: std::path::PathBuf,
+    },
+    FileWrite {
+        path: std::path::PathBuf,
+    },
+    NotAn8BitBmp {
+        path: std::path::PathBuf,
+    },
+    NotA256ColorPalette {
+        path: std::path::PathBuf,
+    },
+    RectOutsideOfImage {
+        path: std::path::PathBuf,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        image_width: u32,
+        image_height: u32,
+    },
+    MissingSpriteChannel {
+        chunk_id: iff::IffChunkId,
+        frame_index: i32,
+        channel: spr::SpriteChannelType,
+    },
+    /// A chunk referenced by another chunk of the description is not part of it.
+    MissingChunk {
+        chunk_type: [u8; 4],
+        chunk_id: iff::IffChunkId,
+        used_by_chunk_type: [u8; 4],
+        used_by_chunk_id: iff::IffChunkId,
+        used_by_chunk_label: String,
+        item_list_index: Option<usize>,
+    },
+    MissingSpriteFrame {
+        chunk_type: [u8; 4],
+        chunk_id: iff::IffChunkId,
+        frame_index: i32,
+        used_by_chunk_id: iff::IffChunkId,
+        used_by_chunk_label: String,
+        item_list_index: usize,
+    },
+    DuplicateChunkId {
+        chunk_type: [u8; 4],
+        chunk_id: iff::IffChunkId,
+    },
+    DuplicateLanguage {
+        chunk_id: iff::IffChunkId,
+        chunk_label: String,
+        language_code: u8,
+        variant: Option<String>,
+    },
+    /// A row of an SPR2 frame needs a count or size that does not fit into the 13 bits of a row command.
+    RowCommandOverflow {
+        chunk_id: iff::IffChunkId,
+        frame_index: i32,
+        row: usize,
+    },
+    UnsupportedVersion {
+        chunk_type: [u8; 4],
+        chunk_id: iff::IffChunkId,
+        chunk_label: String,
+        version: u32,
+    },
+    MissingGuids {
+        path: std::path::PathBuf,
+    },
+    GuidMismatch {
+        input_path: std::path::PathBuf,
+        output_path: std::path::PathBuf,
+    },
+    VariantGuidsMatch,
+    MissingReplacementGuid {
+        chunk_id: iff::IffChunkId,
+        chunk_label: String,
+    },
+}
+
+// the names the messages use for chunk types
+fn chunk_type_name(chunk_type: &[u8; 4]) -> &'static str {
+    match chunk_type {
+        b"DGRP" => "draw group",
+        b"OBJD" => "object definition",
+        b"SLOT" => "slot",
+        b"SPR#" | b"SPR2" => "sprite",
+        _ => "chunk",
+    }
+}
+
+impl Error {
+    /// A stable code for the kind of error, used by `--message-format json`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::FileRead { .. } => "file-read",
+            Error::FileWrite { .. } => "file-write",
+            Error::NotAn8BitBmp { .. } => "bmp-not-8-bit",
+            Error::NotA256ColorPalette { .. } => "palette-not-256-colors",
+            Error::RectOutsideOfImage { .. } => "rect-outside-of-image",
+            Error::MissingSpriteChannel { .. } => "missing-sprite-channel",
+            Error::MissingChunk { .. } => "missing-chunk",
+            Error::MissingSpriteFrame { .. } => "missing-sprite-frame",
+            Error::DuplicateChunkId { .. } => "duplicate-chunk-id",
+            Error::DuplicateLanguage { .. } => "duplicate-language",
+            Error::RowCommandOverflow { .. } => "row-command-overflow",
+            Error::UnsupportedVersion { .. } => "unsupported-version",
+            Error::MissingGuids { .. } => "missing-guids",
+            Error::GuidMismatch { .. } => "guid-mismatch",
+            Error::VariantGuidsMatch => "variant-guids-match",
+            Error::MissingReplacementGuid { .. } => "missing-replacement-guid",
+        }
+    }
+
+    /// The ID of the chunk the error is about, if any.
+    pub fn chunk_id(&self) -> Option<iff::IffChunkId> {
+        match self {
+            Error::MissingSpriteChannel { chunk_id, .. }
+            | Error::MissingChunk { chunk_id, .. }
+            | Error::MissingSpriteFrame { chunk_id, .. }
+            | Error::DuplicateChunkId { chunk_id, .. }
+            | Error::DuplicateLanguage { chunk_id, .. }
+            | Error::RowCommandOverflow { chunk_id, .. }
+            | Error::UnsupportedVersion { chunk_id, .. }
+            | Error::MissingReplacementGuid { chunk_id, .. } => Some(*chunk_id),
+            _ => None,
+        }
+    }
+
+    /// The file the error is about, if any.
+    pub fn path(&self) -> Option<&std::path::Path> {
+        match self {
+            Error::FileRead { path }
+            | Error::FileWrite { path }
+            | Error::NotAn8BitBmp { path }
+            | Error::NotA256ColorPalette { path }
+            | Error::RectOutsideOfImage { path, .. }
+            | Error::MissingGuids { path } => Some(path),
+            Error::GuidMismatch { output_path, .. } => Some(output_path),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::FileRead { path } => write!(f, "Failed to read {}", path.display()),
+            Error::FileWrite { path } => write!(f, "Failed to write {}", path.display()),
+            Error::NotAn8BitBmp { path } => write!(f, "{} is not an 8-bit bmp", path.display()),
+            Error::NotA256ColorPalette { path } => write!(f, "{} does not have a 256 color palette", path.display()),
+            Error::RectOutsideOfImage {
+                path,
+                x,
+                y,
+                width,
+                height,
+                image_width,
+                image_height,
+            } => write!(
+                f,
+                "{}x{} rectangle at {},{} is outside of the {}x{} image {}",
+                width,
+                height,
+                x,
+                y,
+                image_width,
+                image_height,
+                path.display()
+            ),
+            Error::MissingSpriteChannel {
+                chunk_id,
+                frame_index,
+                channel,
+                ..
+            } => write!(
+                f,
+                "Failed to find {} channel in sprite id: {} frame: {}",
+                channel,
+                chunk_id.as_i16(),
+                frame_index
+            ),
+            Error::MissingChunk {
+                chunk_type,
+                chunk_id,
+                used_by_chunk_type,
+                used_by_chunk_id,
+                used_by_chunk_label,
+                item_list_index,
+            } => {
+                // the sprites object definitions refer to are the dynamic sprites
+                let name = match (chunk_type, used_by_chunk_type) {
+                    (b"SPR2", b"OBJD") => "dynamic sprite",
+                    _ => chunk_type_name(chunk_type),
+                };
+                write!(
+                    f,
+                    "failed to find {} {} used in {} {} {}",
+                    name,
+                    chunk_id.as_i16(),
+                    chunk_type_name(used_by_chunk_type),
+                    used_by_chunk_id.as_i16(),
+                    used_by_chunk_label
+                )?;
+                if let Some(item_list_index) = item_list_index {
+                    write!(f, " item list {}", item_list_index)?;
+                }
+                Ok(())
+            }
+            Error::MissingSpriteFrame {
+                chunk_id,
+                frame_index,
+                used_by_chunk_id,
+                used_by_chunk_label,
+                item_list_index,
+                ..
+            } => write!(
+                f,
+                "failed to find frame {} of sprite {} used in draw group {} {} item list {}",
+                frame_index,
+                chunk_id.as_i16(),
+                used_by_chunk_id.as_i16(),
+                used_by_chunk_label,
+                item_list_index
+            ),
+            Error::DuplicateChunkId { chunk_type, chunk_id } => {
+                let description = match chunk_type {
+                    b"BHAV" => "behaviours contain more than one entry",
+                    b"STR#" => "strings contain more than one string table",
+                    b"CTSS" => "catalog contains more than one entry",
+                    b"DGRP" => "draw groups contain more than one draw group",
+                    b"SLOT" => "slots contain more than one slot",
+                    b"SPR#" | b"SPR2" => "sprites contain more than one sprite",
+                    _ => "description contains more than one chunk",
+                };
+                write!(f, "{} with the chunk ID {}", description, chunk_id.as_i16())
+            }
+            Error::DuplicateLanguage {
+                chunk_id,
+                chunk_label,
+                language_code,
+                variant,
+            } => write!(
+                f,
+                "catalog strings {} {} contain language {} more than once{}",
+                chunk_id.as_i16(),
+                chunk_label,
+                language_code,
+                variant.as_ref().map(|x| format!(" for variant {x}")).unwrap_or_default()
+            ),
+            Error::RowCommandOverflow {
+                chunk_id,
+                frame_index,
+                row,
+            } => write!(
+                f,
+                "Row {} of frame {} in sprite {} is too long for an SPR2 row command",
+                row,
+                frame_index,
+                chunk_id.as_i16()
+            ),
+            Error::UnsupportedVersion {
+                chunk_type,
+                chunk_id,
+                chunk_label,
+                version,
+            } => write!(
+                f,
+                "Unsupported version {} in {} {} {}",
+                version,
+                chunk_type_name(chunk_type),
+                chunk_id.as_i16(),
+                chunk_label
+            ),
+            Error::MissingGuids { path } => write!(f, "Failed to find any GUIDs in {}", path.display()),
+            Error::GuidMismatch {
+                input_path,
+                output_path,
+            } => write!(
+                f,
+                "GUIDs in {} do not match GUIDs in {}",
+                input_path.display(),
+                output_path.display()
+            ),
+            Error::VariantGuidsMatch => write!(f, "GUIDs in iff files match. Variant objects must have unique GUIDs"),
+            Error::MissingReplacementGuid { chunk_id, chunk_label } => write!(
+                f,
+                "Failed to find replacement GUID for object {} {}",
+                chunk_id.as_i16(),
+                chunk_label
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub fn file_read_error(file_path: &std::path::Path) -> Error {
+    Error::FileRead {
+        path: file_path.to_owned(),
+    }
 }
 
-pub fn file_write_error(file_path: &std::path::Path) -> String {
-    format!("Failed to write {}", file_path.display())
+pub fn file_write_error(file_path: &std::path::Path) -> Error {
+    Error::FileWrite {
+        path: file_path.to_owned(),
+    }
 }
@@ -0,0 +1,211 @@
+use crate::error;
+use crate::iff;
+use crate::message;
+
+use anyhow::Context;
+
+pub const FAR_HEADER_SIZE: usize = 16;
+pub const FAR_VERSION: u32 = 1;
+
+#[derive(binrw::BinRead, binrw::BinWrite)]
+#[brw(little, magic = b"FAR!byAZ")]
+struct FarHeader {
+    version: u32,
+    manifest_address: u32,
+}
+
+#[binrw::binrw]
+#[brw(little)]
+struct FarManifestEntry {
+    size: u32,
+    compressed_size: u32,
+    address: u32,
+    #[bw(try_calc(u32::try_from(file_name.len())))]
+    file_name_length: u32,
+    #[br(count = file_name_length)]
+    file_name: Vec<u8>,
+}
+
+#[binrw::binrw]
+#[brw(little)]
+struct FarManifest {
+    #[bw(try_calc(u32::try_from(entries.len())))]
+    entry_count: u32,
+    #[br(count = entry_count)]
+    entries: Vec<FarManifestEntry>,
+}
+
+#[derive(serde::Serialize)]
+pub struct FarEntry {
+    pub file_name: String,
+    pub address: u32,
+    pub size: u32,
+}
+
+pub struct Far {
+    pub entries: Vec<FarEntry>,
+    data: Vec<u8>,
+}
+
+impl Far {
+    pub fn entry(&self, file_name: &str) -> Option<&FarEntry> {
+        let file_name = file_name.replace('\\', "/");
+        self.entries.iter().find(|x| x.file_name.replace('\\', "/").eq_ignore_ascii_case(&file_name))
+    }
+
+    pub fn entry_data(&self, entry: &FarEntry) -> &[u8] {
+        let address = usize::try_from(entry.address).unwrap();
+        &self.data[address..address + usize::try_from(entry.size).unwrap()]
+    }
+}
+
+pub fn read_far_file(far_file_path: &std::path::Path) -> anyhow::Result<Far> {
+    let data = std::fs::read(far_file_path).with_context(|| error::file_read_error(far_file_path))?;
+
+    let mut cursor = std::io::Cursor::new(&data);
+    use binrw::BinReaderExt;
+    let header: FarHeader = cursor.read_le().with_context(|| far_decode_error(far_file_path))?;
+    anyhow::ensure!(
+        header.version == FAR_VERSION,
+        "Unsupported version {} in far file {}",
+        header.version,
+        far_file_path.display()
+    );
+    cursor.set_position(u64::from(header.manifest_address));
+    let manifest: FarManifest = cursor.read_le().with_context(|| far_decode_error(far_file_path))?;
+
+    let mut entries = Vec::with_capacity(manifest.entries.len());
+    for entry in manifest.entries {
+        let file_name = String::from_utf8_lossy(&entry.file_name).into_owned();
+        anyhow::ensure!(
+            entry.size == entry.compressed_size,
+            "Entry {} in far file {} is compressed",
+            file_name,
+            far_file_path.display()
+        );
+        anyhow::ensure!(
+            u64::from(entry.address) + u64::from(entry.size) <= data.len() as u64,
+            "Entry {} in far file {} is outside of the file",
+            file_name,
+            far_file_path.display()
+        );
+        entries.push(FarEntry {
+            file_name,
+            address: entry.address,
+            size: entry.size,
+        });
+    }
+
+    Ok(Far { entries, data })
+}
+
+pub fn write_far_file(far_file_path: &std::path::Path, entries: &[(String, Vec<u8>)]) -> anyhow::Result<()> {
+    let mut manifest_entries = Vec::with_capacity(entries.len());
+    let mut address = u32::try_from(FAR_HEADER_SIZE).unwrap();
+    for (file_name, data) in entries {
+        let size = u32::try_from(data.len()).with_context(|| format!("{} is too large", file_name))?;
+        manifest_entries.push(FarManifestEntry {
+            size,
+            compressed_size: size,
+            address,
+            file_name: file_name.as_bytes().to_vec(),
+        });
+        address = address
+            .checked_add(size)
+            .with_context(|| format!("Far file {} is too large", far_file_path.display()))?;
+    }
+
+    let header = FarHeader {
+        version: FAR_VERSION,
+        manifest_address: address,
+    };
+    let manifest = FarManifest {
+        entries: manifest_entries,
+    };
+
+    let mut far_data = std::io::Cursor::new(Vec::new());
+    use binrw::BinWriterExt;
+    far_data.write_le(&header).with_context(|| error::file_write_error(far_file_path))?;
+    for (_, data) in entries {
+        use std::io::Write;
+        far_data.write_all(data).with_context(|| error::file_write_error(far_file_path))?;
+    }
+    far_data.write_le(&manifest).with_context(|| error::file_write_error(far_file_path))?;
+
+    std::fs::write(far_file_path, far_data.into_inner()).with_context(|| error::file_write_error(far_file_path))
+}
+
+pub fn pack(far_file_path: &std::path::Path, iff_file_paths: &[std::path::PathBuf]) -> anyhow::Result<()> {
+    let mut entries: Vec<(String, Vec<u8>)> = Vec::with_capacity(iff_file_paths.len());
+    for iff_file_path in iff_file_paths {
+        let file_name = iff_file_path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .with_context(|| format!("Failed to get file name of {}", iff_file_path.display()))?
+            .to_owned();
+        anyhow::ensure!(
+            !entries.iter().any(|(x, _)| x.eq_ignore_ascii_case(&file_name)),
+            "More than one file is named {}",
+            file_name
+        );
+
+        // make sure only valid iff files end up in the archive
+        iff::read_iff_file(iff_file_path)?;
+
+        let data = std::fs::read(iff_file_path).with_context(|| error::file_read_error(iff_file_path))?;
+        entries.push((file_name, data));
+    }
+
+    write_far_file(far_file_path, &entries)
+}
+
+pub fn unpack(far_file_path: &std::path::Path, output_directory: &std::path::Path) -> anyhow::Result<()> {
+    let far = read_far_file(far_file_path)?;
+
+    for entry in &far.entries {
+        let entry_path = std::path::Path::new(&entry.file_name);
+        anyhow::ensure!(
+            entry_path.components().all(|x| matches!(x, std::path::Component::Normal(_))),
+            "Entry {} in far file {} is not a relative file path",
+            entry.file_name,
+            far_file_path.display()
+        );
+
+        let output_file_path = output_directory.join(entry_path);
+        if let Some(parent_directory) = output_file_path.parent() {
+            std::fs::create_dir_all(parent_directory)
+                .with_context(|| format!("Failed to create directory {}", parent_directory.display()))?;
+        }
+        std::fs::write(&output_file_path, far.entry_data(entry))
+            .with_context(|| error::file_write_error(&output_file_path))?;
+    }
+
+    Ok(())
+}
+
+pub fn split_far_entry_path(file_path: &std::path::Path) -> Option<(&std::path::Path, String)> {
+    // entries inside of far archives are addressed like a file in a directory e.g. Objects.far/chair.iff
+    file_path.ancestors().skip(1).find_map(|far_file_path| {
+        let is_far_file =
+            far_file_path.extension().is_some_and(|x| x.eq_ignore_ascii_case("far")) && far_file_path.is_file();
+        if !is_far_file {
+            return None;
+        }
+        let entry_name = file_path.strip_prefix(far_file_path).ok()?.to_str()?.to_owned();
+        Some((far_file_path, entry_name))
+    })
+}
+
+pub fn list(far_file_path: &std::path::Path) -> anyhow::Result<()> {
+    let far = read_far_file(far_file_path)?;
+    let mut text = vec![format!("{:>10} {:>10} file name", "size", "address")];
+    for entry in &far.entries {
+        text.push(format!("{:>10} {:>10} {}", entry.size, entry.address, entry.file_name));
+    }
+    message::data(&text.join("\n"), &far.entries);
+    Ok(())
+}
+
+fn far_decode_error(file_path: &std::path::Path) -> String {
+    format!("Failed to decode far file {}", file_path.display())
+}
@@ -0,0 +1,9 @@
+pub fn generate_guid(used_guids: &std::collections::HashSet<i32>) -> i32 {
+    // a GUID of 0 means no object
+    loop {
+        let guid: i32 = rand::random();
+        if guid != 0 && !used_guids.contains(&guid) {
+            return guid;
+        }
+    }
+}
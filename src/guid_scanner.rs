@@ -0,0 +1,242 @@
+use crate::compiler;
+use crate::far;
+use crate::iff;
+use crate::iff_description;
+use crate::message;
+
+use anyhow::Context;
+
+pub struct GuidOwner {
+    file_path: std::path::PathBuf,
+    chunk_id: iff::IffChunkId,
+    chunk_label: String,
+}
+
+impl std::fmt::Display for GuidOwner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (OBJD {} {})",
+            self.file_path.display(),
+            self.chunk_id.as_i16(),
+            self.chunk_label
+        )
+    }
+}
+
+pub type GuidOwners = std::collections::BTreeMap<i32, Vec<GuidOwner>>;
+
+/// The names `compile-advanced` gives the iff file of an object.
+#[derive(Clone, Copy)]
+pub struct CompiledNames<'a> {
+    pub format_string: &'a str,
+    pub creator_name: &'a str,
+    pub variant_name: Option<&'a str>,
+}
+
+fn find_object_files(directory: &std::path::Path, file_paths: &mut Vec<std::path::PathBuf>) -> anyhow::Result<()> {
+    let entries =
+        std::fs::read_dir(directory).with_context(|| format!("Failed to read directory {}", directory.display()))?;
+    for entry in entries {
+        let path = entry.with_context(|| format!("Failed to read directory {}", directory.display()))?.path();
+        if path.is_dir() {
+            find_object_files(&path, file_paths)?;
+        } else if path.extension().is_some_and(|x| x.eq_ignore_ascii_case("iff") || x.eq_ignore_ascii_case("far")) {
+            file_paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn add_guid_owners(guid_owners: &mut GuidOwners, iff: &iff::Iff, file_path: &std::path::Path) -> anyhow::Result<()> {
+    for (chunk_id, guid) in iff::map_guids(&iff.chunks)? {
+        let chunk_label = iff
+            .chunks
+            .iter()
+            .find(|x| x.header.chunk_type() == b"OBJD" && x.header.id() == chunk_id)
+            .map(|x| x.header.label())
+            .unwrap_or_default();
+        guid_owners.entry(guid).or_default().push(GuidOwner {
+            file_path: file_path.to_owned(),
+            chunk_id,
+            chunk_label,
+        });
+    }
+    Ok(())
+}
+
+fn scan_file(guid_owners: &mut GuidOwners, file_path: &std::path::Path) -> anyhow::Result<()> {
+    let is_far_file = file_path.extension().is_some_and(|x| x.eq_ignore_ascii_case("far"));
+    if !is_far_file {
+        let iff = iff::read_iff_file(file_path)?;
+        return add_guid_owners(guid_owners, &iff, file_path);
+    }
+
+    let far = far::read_far_file(file_path)?;
+    for entry in &far.entries {
+        if !entry.file_name.to_ascii_lowercase().ends_with(".iff") {
+            continue;
+        }
+        let entry_file_path = file_path.join(&entry.file_name);
+        let iff = iff::read_iff(far.entry_data(entry), &entry_file_path)?;
+        add_guid_owners(guid_owners, &iff, &entry_file_path)?;
+    }
+    Ok(())
+}
+
+fn game_object_directories(the_sims_directory: &std::path::Path) -> anyhow::Result<Vec<std::path::PathBuf>> {
+    // the base game objects are in GameData and every expansion pack has its own directory
+    let mut directories = vec![the_sims_directory.join("GameData")];
+    let entries = std::fs::read_dir(the_sims_directory)
+        .with_context(|| format!("Failed to read directory {}", the_sims_directory.display()))?;
+    for entry in entries {
+        let path = entry.with_context(|| format!("Failed to read directory {}", the_sims_directory.display()))?.path();
+        let is_expansion_pack_directory = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .is_some_and(|x| x.to_ascii_lowercase().starts_with("expansionpack"));
+        if path.is_dir() && is_expansion_pack_directory {
+            directories.push(path);
+        }
+    }
+    Ok(directories.into_iter().filter(|x| x.is_dir()).collect())
+}
+
+pub fn scan_guid_owners(
+    the_sims_directory: &std::path::Path,
+    include_game_objects: bool,
+) -> anyhow::Result<GuidOwners> {
+    let mut directories = vec![the_sims_directory.join("downloads")];
+    if include_game_objects {
+        directories.extend(game_object_directories(the_sims_directory)?);
+    }
+
+    let mut file_paths = Vec::new();
+    // a fresh install has no downloads directory until something is installed
+    for directory in directories.iter().filter(|x| x.is_dir()) {
+        find_object_files(directory, &mut file_paths)?;
+    }
+    file_paths.sort();
+
+    let mut guid_owners = GuidOwners::new();
+    for file_path in &file_paths {
+        if let Err(error) = scan_file(&mut guid_owners, file_path) {
+            message::warning(&format!("Skipping {}: {:#}", file_path.display(), error), None);
+        }
+    }
+    Ok(guid_owners)
+}
+
+// the walked paths and the paths of a description can name the same file in different ways
+fn is_same_file(file_path: &std::path::Path, other_file_path: &std::path::Path) -> bool {
+    match (file_path.canonicalize(), other_file_path.canonicalize()) {
+        (Ok(file_path), Ok(other_file_path)) => file_path == other_file_path,
+        _ => file_path == other_file_path,
+    }
+}
+
+fn find_conflicts(
+    iff_description: &iff_description::IffDescription,
+    own_iff_file_paths: &[&std::path::Path],
+    guid_owners: &GuidOwners,
+) -> usize {
+    let mut conflict_count = 0;
+    for object_definition in &iff_description.object_definitions.object_definitions {
+        // the files the description is compiled from and to are allowed to have its GUIDs
+        let conflicting_owners: Vec<_> = guid_owners
+            .get(&object_definition.guid)
+            .into_iter()
+            .flatten()
+            .filter(|x| !own_iff_file_paths.iter().any(|own| is_same_file(&x.file_path, own)))
+            .collect();
+        if conflicting_owners.is_empty() {
+            continue;
+        }
+        let mut conflict = format!(
+            "GUID 0x{:08x} of object definition {} {} is already used by:",
+            object_definition.guid,
+            object_definition.chunk_id.as_i16(),
+            object_definition.chunk_label
+        );
+        for owner in conflicting_owners {
+            conflict.push_str(&format!("\n    {owner}"));
+        }
+        message::warning(&conflict, None);
+        conflict_count += 1;
+    }
+    conflict_count
+}
+
+/// Fails when a GUID of the description is used by an object in the downloads directory other than the files it is
+/// compiled from and to.
+pub fn check_guids(
+    the_sims_directory: &std::path::Path,
+    iff_description: &iff_description::IffDescription,
+    own_iff_file_paths: &[&std::path::Path],
+) -> anyhow::Result<()> {
+    let guid_owners = scan_guid_owners(the_sims_directory, false)?;
+    let conflict_count = find_conflicts(iff_description, own_iff_file_paths, &guid_owners);
+    anyhow::ensure!(
+        conflict_count == 0,
+        "{} GUIDs of {} are already used by other objects",
+        conflict_count,
+        iff_description.iff_file_path_relative
+    );
+    Ok(())
+}
+
+/// Reports every GUID used by more than one file. With a description, also fails when its GUIDs are used by files
+/// other than the one it compiles to, or the files named by `compile-advanced` when its format string is given.
+pub fn scan_guids(
+    the_sims_directory: &std::path::Path,
+    include_game_objects: bool,
+    xml_file_path: Option<&std::path::Path>,
+    compiled_names: Option<CompiledNames>,
+) -> anyhow::Result<()> {
+    let guid_owners = scan_guid_owners(the_sims_directory, include_game_objects)?;
+
+    let mut duplicate_count = 0;
+    for (guid, owners) in guid_owners.iter().filter(|(_, owners)| owners.len() > 1) {
+        let mut duplicate = format!("GUID 0x{guid:08x} is used by:");
+        for owner in owners {
+            duplicate.push_str(&format!("\n    {owner}"));
+        }
+        message::warning(&duplicate, None);
+        duplicate_count += 1;
+    }
+    if duplicate_count == 0 {
+        message::info(&format!("No duplicate GUIDs found among {} GUIDs", guid_owners.len()));
+    }
+
+    if let Some(xml_file_path) = xml_file_path {
+        let iff_description = iff_description::IffDescription::open(xml_file_path)
+            .with_context(|| format!("Failed to open xml file {}", xml_file_path.display()))?;
+        let own_iff_file_paths = match compiled_names {
+            Some(compiled_names) => {
+                let object_name = xml_file_path.file_stem().unwrap_or_default().to_string_lossy();
+                compiler::formatted_iff_file_paths(
+                    &the_sims_directory.join("downloads"),
+                    compiled_names.format_string,
+                    compiled_names.creator_name,
+                    &object_name,
+                    compiled_names.variant_name,
+                )?
+            }
+            None => vec![iff_description.iff_file_path(the_sims_directory)],
+        };
+        let own_iff_file_paths: Vec<_> = own_iff_file_paths.iter().map(|x| x.as_path()).collect();
+        let conflict_count = find_conflicts(&iff_description, &own_iff_file_paths, &guid_owners);
+        anyhow::ensure!(
+            conflict_count == 0,
+            "{} GUIDs in {} are already used by other objects",
+            conflict_count,
+            xml_file_path.display()
+        );
+        message::info(&format!(
+            "No GUIDs in {} are used by other objects",
+            xml_file_path.display()
+        ));
+    }
+
+    Ok(())
+}
@@ -1,7 +1,13 @@
+use crate::backup;
+use crate::bhav;
 use crate::error;
+use crate::far;
 use crate::iff_description;
+use crate::message;
 use crate::palt;
 use crate::spr;
+use crate::sprite;
+use crate::windows_1252;
 
 use anyhow::Context;
 
@@ -10,12 +16,17 @@ pub const IFF_HEADER_SIZE: usize = 64;
 pub const IFF_CHUNK_HEADER_SIZE: usize = 76;
 pub const IFF_CHUNK_LABEL_SIZE: usize = 64;
 
+/// The ID of a chunk, unique per chunk type within an iff file.
 #[derive(
     Copy, Clone, Debug, Eq, PartialEq, Hash, binrw::BinRead, binrw::BinWrite, serde::Deserialize, serde::Serialize,
 )]
 pub struct IffChunkId(i16);
 
 impl IffChunkId {
+    pub fn new(id: i16) -> IffChunkId {
+        IffChunkId(id)
+    }
+
     pub fn as_i16(self) -> i16 {
         self.0
     }
@@ -45,19 +56,25 @@ pub struct IffChunkHeader {
 }
 
 impl IffChunkHeader {
+    /// Creates a header for a chunk with `data_size` bytes of data. The label is encoded as Windows-1252.
     pub fn new(chunk_type: &[u8; 4], data_size: usize, id: IffChunkId, label: &str) -> anyhow::Result<IffChunkHeader> {
         let label = {
             let mut label_buffer = [0u8; IFF_CHUNK_LABEL_SIZE];
-            let cstring_label = std::ffi::CString::new(label).unwrap();
-            let cstring_label = cstring_label.to_bytes_with_nul();
+            let encoded_label = windows_1252::encode(label).context("Failed to encode chunk label")?;
             anyhow::ensure!(
-                cstring_label.len() < IFF_CHUNK_LABEL_SIZE,
-                format!(
-                    "Chunk label \"{}\" is larger than {} bytes",
-                    label, IFF_CHUNK_LABEL_SIZE
-                )
+                !encoded_label.contains(&0),
+                "Chunk label \"{}\" contains a null character",
+                label
             );
-            label_buffer[..cstring_label.len()].copy_from_slice(cstring_label);
+            // the label is null terminated so the last byte is always 0
+            anyhow::ensure!(
+                encoded_label.len() < IFF_CHUNK_LABEL_SIZE,
+                "Chunk label \"{}\" is {} bytes long, the limit is {} bytes",
+                label,
+                encoded_label.len(),
+                IFF_CHUNK_LABEL_SIZE - 1
+            );
+            label_buffer[..encoded_label.len()].copy_from_slice(&encoded_label);
             label_buffer
         };
         Ok(IffChunkHeader {
@@ -68,8 +85,46 @@ impl IffChunkHeader {
             label,
         })
     }
+
+    pub fn chunk_type(&self) -> &[u8; 4] {
+        &self.chunk_type
+    }
+
+    pub fn id(&self) -> IffChunkId {
+        self.id
+    }
+
+    pub fn flags(&self) -> i16 {
+        self.flags
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn label(&self) -> String {
+        let label_length = self.label.iter().position(|x| *x == 0).unwrap();
+        windows_1252::decode(&self.label[..label_length])
+    }
 }
 
+/// A file name for a chunk made from its ID and label.
+pub fn chunk_file_name(chunk_id: IffChunkId, chunk_label: &str) -> String {
+    let chunk_label: String = chunk_label
+        .chars()
+        .map(|x| {
+            if x.is_control() || matches!(x, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') {
+                '_'
+            } else {
+                x
+            }
+        })
+        .collect();
+    let file_name = format!("{} {}", chunk_id.as_i16(), chunk_label);
+    file_name.trim_end_matches(['.', ' ']).to_owned()
+}
+
+/// A chunk header followed by the little endian chunk data.
 #[derive(Clone, binrw::BinRead, binrw::BinWrite)]
 pub struct IffChunk {
     pub header: IffChunkHeader,
@@ -77,27 +132,116 @@ pub struct IffChunk {
     pub data: Vec<u8>,
 }
 
+/// An iff file, the container for all of an object's chunks.
 #[derive(binrw::BinRead, binrw::BinWrite)]
 #[brw(magic = b"IFF FILE 2.5:TYPE FOLLOWED BY SIZE\0 JAMIE DOORNBOS & MAXIS 1")]
-struct Iff {
+pub struct Iff {
     #[brw(big)]
     rsmp_address: u32,
     #[br(parse_with = binrw::helpers::until_eof)]
-    chunks: Vec<IffChunk>,
+    pub chunks: Vec<IffChunk>,
+}
+
+impl Iff {
+    pub fn rsmp_address(&self) -> u32 {
+        self.rsmp_address
+    }
+
+    pub fn chunk_addresses(&self) -> Vec<u32> {
+        self.chunks
+            .iter()
+            .scan(IFF_HEADER_SIZE as u32, |address, chunk| {
+                let chunk_address = *address;
+                *address += chunk.header.size;
+                Some(chunk_address)
+            })
+            .collect()
+    }
+}
+
+pub struct IffChunkReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> IffChunkReader<'a> {
+    pub fn new(data: &'a [u8]) -> IffChunkReader<'a> {
+        IffChunkReader { data, position: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn seek(&mut self, position: usize) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            position <= self.data.len(),
+            "Chunk data offset {} is past the end of the chunk",
+            position
+        );
+        self.position = position;
+        Ok(())
+    }
+
+    pub fn read_bytes(&mut self, count: usize) -> anyhow::Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .with_context(|| format!("Unexpected end of chunk data at offset {}", self.position))?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    pub fn read_u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_u16(&mut self) -> anyhow::Result<u16> {
+        Ok(u16::from_le_bytes(self.read_bytes(2)?.try_into().unwrap()))
+    }
+
+    pub fn read_i16(&mut self) -> anyhow::Result<i16> {
+        Ok(i16::from_le_bytes(self.read_bytes(2)?.try_into().unwrap()))
+    }
+
+    pub fn read_u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn read_i32(&mut self) -> anyhow::Result<i32> {
+        Ok(i32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn read_f32(&mut self) -> anyhow::Result<f32> {
+        Ok(f32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+}
+
+/// Reads an iff file. Paths like `Objects.far/chair.iff` read the entry of a far archive.
+pub fn read_iff_file(iff_file_path: &std::path::Path) -> anyhow::Result<Iff> {
+    if let Some((far_file_path, entry_name)) = far::split_far_entry_path(iff_file_path) {
+        let far = far::read_far_file(far_file_path)?;
+        let entry = far
+            .entry(&entry_name)
+            .with_context(|| format!("Failed to find {} in far file {}", entry_name, far_file_path.display()))?;
+        return read_iff(far.entry_data(entry), iff_file_path);
+    }
+
+    let iff_data = std::fs::read(iff_file_path).with_context(|| error::file_read_error(iff_file_path))?;
+    read_iff(&iff_data, iff_file_path)
 }
 
-fn read_iff_file(iff_file_path: &std::path::Path) -> anyhow::Result<Iff> {
-    let mut iff_file = std::fs::File::open(iff_file_path).with_context(|| error::file_read_error(iff_file_path))?;
+/// Reads an iff file from memory, the path is only used in error messages.
+pub fn read_iff(iff_data: &[u8], iff_file_path: &std::path::Path) -> anyhow::Result<Iff> {
     use binrw::BinReaderExt;
-    let iff: Iff = iff_file.read_ne().with_context(|| iff_decode_error(iff_file_path))?;
+    let iff: Iff = std::io::Cursor::new(iff_data).read_ne().with_context(|| iff_decode_error(iff_file_path))?;
 
     {
         // binrw's until_eof attribute does not distinguish between eof and failing to parse an element
         // manually check that all the file was read and the chunk sizes are correct
         let chunk_sizes = iff.chunks.iter().fold(0u32, |acc, x| acc + x.header.size);
-        use std::io::Seek;
         anyhow::ensure!(
-            IFF_HEADER_SIZE as u64 + u64::from(chunk_sizes) == iff_file.seek(std::io::SeekFrom::End(0)).unwrap(),
+            IFF_HEADER_SIZE as u64 + u64::from(chunk_sizes) == iff_data.len() as u64,
             "found unknown data at end of iff file {}",
             iff_file_path.display()
         );
@@ -106,22 +250,28 @@ fn read_iff_file(iff_file_path: &std::path::Path) -> anyhow::Result<Iff> {
     Ok(iff)
 }
 
-fn map_guids(chunks: &[IffChunk]) -> std::collections::HashMap<IffChunkId, i32> {
+/// Maps the ID of every object definition to its GUID.
+pub fn map_guids(chunks: &[IffChunk]) -> anyhow::Result<std::collections::HashMap<IffChunkId, i32>> {
     let mut guids = std::collections::HashMap::new();
     for chunk in chunks {
         if &chunk.header.chunk_type == b"OBJD" {
             const GUID_ADDRESS_OFFSET: usize = 28;
-            let guid = i32::from_le_bytes(
-                chunk.data.get(GUID_ADDRESS_OFFSET..GUID_ADDRESS_OFFSET + 4).unwrap().try_into().unwrap(),
-            );
-            guids.entry(chunk.header.id).or_insert(guid);
+            let guid = chunk.data.get(GUID_ADDRESS_OFFSET..GUID_ADDRESS_OFFSET + 4).with_context(|| {
+                format!(
+                    "Object definition {} {} is too short to contain a GUID",
+                    chunk.header.id().as_i16(),
+                    chunk.header.label()
+                )
+            })?;
+            guids.entry(chunk.header.id).or_insert(i32::from_le_bytes(guid.try_into().unwrap()));
         }
     }
-    guids
+    Ok(guids)
 }
 
 fn create_rsmp_chunk(chunks: &[IffChunk]) -> IffChunk {
-    let mut chunk_descriptions = std::collections::HashMap::new();
+    // a sorted map keeps the rsmp chunk identical between builds
+    let mut chunk_descriptions = std::collections::BTreeMap::new();
     chunks.iter().fold(IFF_HEADER_SIZE as u32, |address, chunk| {
         chunk_descriptions
             .entry(chunk.header.chunk_type)
@@ -171,96 +321,183 @@ fn create_rsmp_chunk(chunks: &[IffChunk]) -> IffChunk {
     }
 }
 
-fn replace_guids_in_bhavs(
+pub fn replace_guids_in_bhavs(
     chunks: &mut [IffChunk],
     input_guids: &std::collections::HashMap<IffChunkId, i32>,
     output_guids: &std::collections::HashMap<IffChunkId, i32>,
 ) {
-    for chunk in chunks {
-        if &chunk.header.chunk_type == b"BHAV" {
-            const INSTRUCTION_SIZE: usize = 12;
-            const PARAMETER_OFFSET: usize = 4;
-            assert!(chunk.data.len() % INSTRUCTION_SIZE == 0);
-            let instruction_count = chunk.data.len() / INSTRUCTION_SIZE;
-            for j in 0..instruction_count {
-                let instruction_address = j * INSTRUCTION_SIZE;
-                let guid_address = instruction_address + PARAMETER_OFFSET;
-                if matches!(chunk.data.get(instruction_address).unwrap(), 31 | 32 | 42) {
-                    let guid =
-                        i32::from_le_bytes(chunk.data.get(guid_address..guid_address + 4).unwrap().try_into().unwrap());
-                    if let Some((objd_id, _)) = input_guids.iter().find(|(_, input_guid)| **input_guid == guid) {
-                        let output_guid = output_guids.get(objd_id).unwrap();
-                        chunk
-                            .data
-                            .get_mut(guid_address..guid_address + 4)
-                            .unwrap()
-                            .copy_from_slice(&output_guid.to_le_bytes());
-                    }
-                }
-            }
+    let guids: std::collections::HashMap<_, _> = input_guids
+        .iter()
+        .filter_map(|(objd_id, input_guid)| Some((*input_guid, *output_guids.get(objd_id)?)))
+        .collect();
+    for chunk in chunks.iter_mut().filter(|x| &x.header.chunk_type == b"BHAV") {
+        bhav::replace_guids_in_behaviour(chunk, &guids);
+    }
+}
+
+/// Options for [`rebuild_iff_file`].
+#[derive(Clone, Copy, Default)]
+pub struct RebuildOptions {
+    /// Give every object definition a fresh GUID, the description must already contain them.
+    pub new_guids: bool,
+    /// Keep replaced chunks at their original position with their original flags.
+    pub preserve_order: bool,
+    /// The number of backups to keep of the output file, 0 disables backups.
+    pub backup_count: usize,
+    /// Build every chunk and print what would change without writing the output file.
+    pub dry_run: bool,
+}
+
+fn merge_chunks_in_original_order(
+    original_chunks: Vec<IffChunk>,
+    regenerated_chunks: Vec<IffChunk>,
+    is_regenerated: impl Fn(&IffChunk) -> bool,
+) -> Vec<IffChunk> {
+    let mut regenerated_chunks: Vec<_> = regenerated_chunks.into_iter().map(Some).collect();
+
+    let mut chunks = Vec::with_capacity(original_chunks.len());
+    for original_chunk in original_chunks {
+        if !is_regenerated(&original_chunk) {
+            chunks.push(original_chunk);
+            continue;
+        }
+        // chunks that are no longer generated are dropped
+        let replacement_chunk = regenerated_chunks.iter_mut().find(|x| {
+            x.as_ref().is_some_and(|x| {
+                x.header.chunk_type == original_chunk.header.chunk_type && x.header.id == original_chunk.header.id
+            })
+        });
+        if let Some(replacement_chunk) = replacement_chunk {
+            let mut replacement_chunk = replacement_chunk.take().unwrap();
+            replacement_chunk.header.flags = original_chunk.header.flags;
+            chunks.push(replacement_chunk);
+        }
+    }
+
+    // only chunks that did not exist before are appended
+    chunks.extend(regenerated_chunks.into_iter().flatten());
+    chunks
+}
+
+/// The files and sprites [`rebuild_iff_file`] builds an iff file from.
+pub struct RebuildInput<'a> {
+    /// The directory sprite and behaviour paths of the description are relative to.
+    pub source_directory: &'a std::path::Path,
+    /// The iff file whose chunks are replaced.
+    pub input_iff_file_path: &'a std::path::Path,
+    /// The iff file that is written.
+    pub output_iff_file_path: &'a std::path::Path,
+    /// Where the output file is read from, which only differs from `output_iff_file_path` in a dry run of a file that
+    /// has not been renamed to its final name yet.
+    pub current_output_iff_file_path: &'a std::path::Path,
+    /// Sprite channels that are used instead of the files they are keyed by.
+    pub split_sprites: &'a sprite::SplitSprites,
+}
+
+impl<'a> RebuildInput<'a> {
+    /// Rebuilds an iff file in place.
+    pub fn in_place(
+        source_directory: &'a std::path::Path,
+        iff_file_path: &'a std::path::Path,
+        split_sprites: &'a sprite::SplitSprites,
+    ) -> RebuildInput<'a> {
+        RebuildInput {
+            source_directory,
+            input_iff_file_path: iff_file_path,
+            output_iff_file_path: iff_file_path,
+            current_output_iff_file_path: iff_file_path,
+            split_sprites,
         }
     }
 }
 
+/// Replaces the chunks of the input iff file with the chunks built from the description and writes the output iff file.
+///
+/// GUID references in behaviours are updated to the GUIDs of the output file, or to the GUIDs of the description when
+/// [`RebuildOptions::new_guids`] is set. The catalog strings are built for `variant_name`.
 pub fn rebuild_iff_file(
-    source_directory: &std::path::Path,
     iff_description: &iff_description::IffDescription,
-    input_iff_file_path: &std::path::Path,
-    output_iff_file_path: &std::path::Path,
+    input: &RebuildInput,
+    variant_name: Option<&str>,
+    options: RebuildOptions,
 ) -> anyhow::Result<()> {
+    let RebuildInput {
+        source_directory,
+        input_iff_file_path,
+        output_iff_file_path,
+        current_output_iff_file_path,
+        split_sprites,
+    } = *input;
+
     let mut iff = read_iff_file(input_iff_file_path)?;
 
-    let (input_guids, output_guids) = {
-        let output_iff = read_iff_file(output_iff_file_path)?;
-        (map_guids(&iff.chunks), map_guids(&output_iff.chunks))
+    let (input_guids, output_guids) = if options.new_guids {
+        // the description has already been given fresh GUIDs
+        let description_guids =
+            iff_description.object_definitions.object_definitions.iter().map(|x| (x.chunk_id, x.guid)).collect();
+        (map_guids(&iff.chunks)?, description_guids)
+    } else {
+        let output_iff = read_iff_file(current_output_iff_file_path)?;
+        (map_guids(&iff.chunks)?, map_guids(&output_iff.chunks)?)
     };
     anyhow::ensure!(
         !input_guids.is_empty(),
-        format!("Failed to find any GUIDs in {}", input_iff_file_path.display())
+        error::Error::MissingGuids {
+            path: input_iff_file_path.to_owned()
+        }
     );
     anyhow::ensure!(
         !output_guids.is_empty(),
-        format!("Failed to find any GUIDs in {}", output_iff_file_path.display())
+        error::Error::MissingGuids {
+            path: current_output_iff_file_path.to_owned()
+        }
     );
     anyhow::ensure!(
         input_guids.len() == output_guids.len() && input_guids.keys().all(|k| output_guids.contains_key(k)),
-        format!(
-            "GUIDs in {} do not match GUIDs in {}",
-            input_iff_file_path.display(),
-            output_iff_file_path.display()
-        )
+        error::Error::GuidMismatch {
+            input_path: input_iff_file_path.to_owned(),
+            output_path: current_output_iff_file_path.to_owned(),
+        }
     );
-    if input_iff_file_path != output_iff_file_path {
-        anyhow::ensure!(
-            input_guids != output_guids,
-            "GUIDs in iff files match. Variant objects must have unique GUIDs"
-        );
+    if input_iff_file_path != current_output_iff_file_path {
+        anyhow::ensure!(input_guids != output_guids, error::Error::VariantGuidsMatch);
     }
 
-    iff.chunks.retain(|x| {
-        !matches!(
-            &x.header.chunk_type,
-            b"DGRP" | b"OBJD" | b"PALT" | b"SLOT" | b"SPR#" | b"SPR2" | b"rsmp"
-        )
-    });
+    let behaviour_ids: std::collections::HashSet<_> =
+        iff_description.behaviours.behaviours.iter().map(|x| x.chunk_id).collect();
+    let string_table_ids: std::collections::HashSet<_> =
+        iff_description.string_tables.string_tables.iter().map(|x| x.chunk_id).collect();
+    let catalog_strings_ids: std::collections::HashSet<_> =
+        iff_description.catalog.catalog_strings.iter().map(|x| x.chunk_id).collect();
+
+    let is_regenerated = |x: &IffChunk| match &x.header.chunk_type {
+        b"DGRP" | b"OBJD" | b"PALT" | b"SLOT" | b"SPR#" | b"SPR2" | b"rsmp" => true,
+        // behaviours without a listing in the description are kept as they are
+        b"BHAV" => behaviour_ids.contains(&x.header.id),
+        b"STR#" => string_table_ids.contains(&x.header.id),
+        b"CTSS" => catalog_strings_ids.contains(&x.header.id),
+        _ => false,
+    };
+    let original_chunks = std::mem::take(&mut iff.chunks);
 
+    let mut chunks = Vec::new();
     for object_definition in &iff_description.object_definitions.object_definitions {
-        let replacement_guid = *output_guids.get(&object_definition.chunk_id).with_context(|| {
-            format!(
-                "Failed to find replacement GUID for object {} {}",
-                object_definition.chunk_id.as_i32(),
-                object_definition.chunk_label
-            )
-        })?;
-        iff.chunks.push(object_definition.to_chunk(Some(replacement_guid))?);
+        let replacement_guid =
+            *output_guids
+                .get(&object_definition.chunk_id)
+                .with_context(|| error::Error::MissingReplacementGuid {
+                    chunk_id: object_definition.chunk_id,
+                    chunk_label: object_definition.chunk_label.clone(),
+                })?;
+        chunks.push(object_definition.to_chunk(Some(replacement_guid))?);
     }
 
     for slot in &iff_description.slots.slots {
-        iff.chunks.push(slot.to_chunk()?);
+        chunks.push(slot.to_chunk()?);
     }
 
     for draw_group in &iff_description.draw_groups.draw_groups {
-        iff.chunks.push(draw_group.to_chunk()?);
+        chunks.push(draw_group.to_chunk()?);
     }
 
     let used_sprite_ids = {
@@ -275,16 +512,55 @@ pub fn rebuild_iff_file(
         used_sprite_ids
     };
 
-    let palt_chunks = palt::create_palt_chunks(source_directory, &iff_description.sprites.sprites)?;
-    iff.chunks.extend(palt_chunks);
+    for behaviour in &iff_description.behaviours.behaviours {
+        chunks.push(behaviour.to_chunk(source_directory)?);
+    }
 
-    for sprite in &iff_description.sprites.sprites {
-        if sprite.sprite_type == spr::SpriteType::Spr2 && !used_sprite_ids.contains(&sprite.chunk_id) {
-            continue;
-        }
-        iff.chunks.push(sprite.to_chunk(source_directory)?);
+    for string_table in &iff_description.string_tables.string_tables {
+        chunks.push(string_table.to_chunk(b"STR#")?);
+    }
+
+    for catalog_strings in &iff_description.catalog.catalog_strings {
+        chunks.push(catalog_strings.to_chunk(variant_name)?);
+    }
+
+    let palt_chunks = palt::create_palt_chunks(source_directory, &iff_description.sprites.sprites, split_sprites)?;
+    chunks.extend(palt_chunks);
+
+    let used_sprites: Vec<_> = iff_description
+        .sprites
+        .sprites
+        .iter()
+        .filter(|x| x.sprite_type != spr::SpriteType::Spr2 || used_sprite_ids.contains(&x.chunk_id))
+        .collect();
+    for (sprite, i) in used_sprites.iter().zip(1..) {
+        chunks.push(sprite.to_chunk(source_directory, split_sprites)?);
+        let chunk_type = match sprite.sprite_type {
+            spr::SpriteType::Spr1 => "SPR#",
+            spr::SpriteType::Spr2 => "SPR2",
+        };
+        message::progress(
+            message::Stage::Compile,
+            i,
+            used_sprites.len(),
+            &message::ProgressLocation {
+                chunk: Some(message::ChunkReference::new(
+                    chunk_type,
+                    sprite.chunk_id,
+                    &sprite.chunk_label,
+                )),
+                ..Default::default()
+            },
+        );
     }
 
+    iff.chunks = if options.preserve_order {
+        merge_chunks_in_original_order(original_chunks, chunks, is_regenerated)
+    } else {
+        original_chunks.into_iter().filter(|x| !is_regenerated(x)).chain(chunks).collect()
+    };
+
+    // the rsmp chunk lists the addresses of all the other chunks so it always goes last
     iff.chunks.push(create_rsmp_chunk(&iff.chunks));
 
     iff.rsmp_address = iff
@@ -301,14 +577,109 @@ pub fn rebuild_iff_file(
 
     replace_guids_in_bhavs(&mut iff.chunks, &input_guids, &output_guids);
 
-    let mut output_iff_file =
-        std::fs::File::create(output_iff_file_path).with_context(|| error::file_write_error(output_iff_file_path))?;
-    use binrw::BinWriterExt;
-    output_iff_file.write_ne(&iff).with_context(|| error::file_write_error(output_iff_file_path))?;
+    if options.dry_run {
+        return print_dry_run_summary(output_iff_file_path, current_output_iff_file_path, &iff);
+    }
+
+    backup::create_backup(output_iff_file_path, options.backup_count)?;
+    write_iff_file(output_iff_file_path, &iff)
+}
+
+fn print_dry_run_summary(
+    output_iff_file_path: &std::path::Path,
+    current_output_iff_file_path: &std::path::Path,
+    iff: &Iff,
+) -> anyhow::Result<()> {
+    let iff_data = encode_iff(output_iff_file_path, iff)?;
+
+    let (current_chunks, current_size) = if current_output_iff_file_path.is_file() {
+        let current_iff = read_iff_file(current_output_iff_file_path)?;
+        let current_size = std::fs::metadata(current_output_iff_file_path)
+            .with_context(|| error::file_read_error(current_output_iff_file_path))?
+            .len();
+        (current_iff.chunks, Some(current_size))
+    } else {
+        (Vec::new(), None)
+    };
+
+    let chunk_name = |chunk: &IffChunk| {
+        format!(
+            "{} {} {}",
+            String::from_utf8_lossy(&chunk.header.chunk_type),
+            chunk.header.id.as_i16(),
+            chunk.header.label()
+        )
+    };
+    fn find_chunk<'a>(chunks: &'a [IffChunk], chunk: &IffChunk) -> Option<&'a IffChunk> {
+        chunks
+            .iter()
+            .find(|x| x.header.chunk_type == chunk.header.chunk_type && x.header.id == chunk.header.id)
+    }
+
+    let (mut written_count, mut replaced_count, mut dropped_count, mut unchanged_count) = (0, 0, 0, 0);
+    for chunk in &iff.chunks {
+        match find_chunk(&current_chunks, chunk) {
+            None => {
+                message::info(&format!("write   {}", chunk_name(chunk)));
+                written_count += 1;
+            }
+            Some(current_chunk)
+                if current_chunk.header.flags == chunk.header.flags
+                    && current_chunk.header.label() == chunk.header.label()
+                    && current_chunk.data == chunk.data =>
+            {
+                unchanged_count += 1;
+            }
+            Some(current_chunk) => {
+                message::info(&format!(
+                    "replace {} ({} bytes -> {} bytes)",
+                    chunk_name(chunk),
+                    current_chunk.header.size,
+                    chunk.header.size
+                ));
+                replaced_count += 1;
+            }
+        }
+    }
+    for current_chunk in current_chunks.iter().filter(|x| find_chunk(&iff.chunks, x).is_none()) {
+        message::info(&format!("drop    {}", chunk_name(current_chunk)));
+        dropped_count += 1;
+    }
+
+    message::info(&format!(
+        "{written_count} chunks written, {replaced_count} replaced, {dropped_count} dropped, {unchanged_count} unchanged"
+    ));
+    match current_size {
+        Some(current_size) => message::info(&format!(
+            "{} would be {} bytes, currently {} bytes",
+            output_iff_file_path.display(),
+            iff_data.len(),
+            current_size
+        )),
+        None => message::info(&format!(
+            "{} would be {} bytes",
+            output_iff_file_path.display(),
+            iff_data.len()
+        )),
+    }
+    message::info("Dry run, nothing was written");
 
     Ok(())
 }
 
+fn encode_iff(iff_file_path: &std::path::Path, iff: &Iff) -> anyhow::Result<Vec<u8>> {
+    let mut iff_data = std::io::Cursor::new(Vec::new());
+    use binrw::BinWriterExt;
+    iff_data.write_ne(iff).with_context(|| error::file_write_error(iff_file_path))?;
+    Ok(iff_data.into_inner())
+}
+
+/// Writes an iff file. An existing file is only replaced once the new file has been written completely.
+pub fn write_iff_file(iff_file_path: &std::path::Path, iff: &Iff) -> anyhow::Result<()> {
+    let iff_data = encode_iff(iff_file_path, iff)?;
+    backup::write_file_atomically(iff_file_path, &iff_data)
+}
+
 fn iff_decode_error(file_path: &std::path::Path) -> String {
     format!("Failed to decode iff file {}", file_path.display())
 }
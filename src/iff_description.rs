@@ -1,12 +1,17 @@
+use crate::bhav;
+use crate::ctss;
 use crate::dgrp;
 use crate::error;
+use crate::guid;
 use crate::objd;
 use crate::slot;
 use crate::spr;
 use crate::sprite;
+use crate::string_table;
 
 use anyhow::Context;
 
+/// The xml description of an object, compatible with the files exported by The Sims Transmogrifier.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct IffDescription {
@@ -60,6 +65,12 @@ pub struct IffDescription {
     pub draw_groups: DrawGroups,
     #[serde(rename = "sprites", deserialize_with = "spr::deserialize_sprites")]
     pub sprites: Sprites,
+    #[serde(default, rename = "behaviours", skip_serializing_if = "Behaviours::is_empty")]
+    pub behaviours: Behaviours,
+    #[serde(default, rename = "strings", skip_serializing_if = "StringTables::is_empty")]
+    pub string_tables: StringTables,
+    #[serde(default, rename = "catalog", skip_serializing_if = "Catalog::is_empty")]
+    pub catalog: Catalog,
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -90,12 +101,98 @@ pub struct Sprites {
     pub sprites: Vec<spr::Sprite>,
 }
 
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Behaviours {
+    #[serde(default, rename = "behaviour")]
+    pub behaviours: Vec<bhav::BehaviourSource>,
+}
+
+impl Behaviours {
+    fn is_empty(&self) -> bool {
+        self.behaviours.is_empty()
+    }
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StringTables {
+    #[serde(default, rename = "stringtable")]
+    pub string_tables: Vec<string_table::StringTable>,
+}
+
+impl StringTables {
+    fn is_empty(&self) -> bool {
+        self.string_tables.is_empty()
+    }
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Catalog {
+    #[serde(default, rename = "catalogstrings")]
+    pub catalog_strings: Vec<ctss::CatalogStrings>,
+}
+
+impl Catalog {
+    fn is_empty(&self) -> bool {
+        self.catalog_strings.is_empty()
+    }
+}
+
 impl IffDescription {
+    pub fn new(
+        iff_file_path_relative: &str,
+        object_definitions: Vec<objd::ObjectDefinition>,
+        slots: Vec<slot::Slot>,
+        draw_groups: Vec<dgrp::DrawGroup>,
+        sprites: Vec<spr::Sprite>,
+    ) -> IffDescription {
+        IffDescription {
+            iff_file_path_relative: iff_file_path_relative.to_owned(),
+            exportobjectdefinitions: 1,
+            exportslots: 1,
+            exportdrawgroups: 1,
+            exportbitmaps: 1,
+            exportsprites: 1,
+            justchangecolors: 0,
+            exportallzooms: 1,
+            smoothsmallzoomcolors: 0,
+            smoothsmallzoomedges: 0,
+            exportexpanded: 0,
+            exportp: 1,
+            exportz: 1,
+            generatez: 0,
+            generatezfar: 0,
+            exporta: 1,
+            generatea: 0,
+            generateasoft: 0,
+            compressbitmaps: 0,
+            createsubdirectories: 1,
+            thingstodo: 0,
+            object_definitions: ObjectDefinitions { object_definitions },
+            slots: Slots { slots },
+            draw_groups: DrawGroups { draw_groups },
+            sprites: Sprites { sprites },
+            behaviours: Behaviours::default(),
+            string_tables: StringTables::default(),
+            catalog: Catalog::default(),
+        }
+    }
+
+    /// The path of the iff file the description compiles to.
+    pub fn iff_file_path(&self, the_sims_directory: &std::path::Path) -> std::path::PathBuf {
+        // descriptions exported by the transmogrifier use windows path separators
+        the_sims_directory.join(self.iff_file_path_relative.replace('\\', "/")).with_extension("iff")
+    }
+
+    /// Reads a description from an xml file.
     pub fn open(xml_file_path: &std::path::Path) -> anyhow::Result<IffDescription> {
         let iff_description = std::fs::read_to_string(xml_file_path)?;
         Ok(quick_xml::de::from_str::<IffDescription>(&iff_description)?)
     }
 
+    /// Writes the description to an xml file.
     pub fn save(&self, xml_file_path: &std::path::Path) -> anyhow::Result<()> {
         let xml_header = include_str!("../res/header.xml");
 
@@ -108,7 +205,10 @@ impl IffDescription {
         Ok(std::fs::write(xml_file_path, &buffer)?)
     }
 
-    pub fn validate(self) -> anyhow::Result<IffDescription> {
+    /// Finds every chunk referenced by the description that is not part of it and every chunk ID used more than once.
+    pub fn find_problems(&self) -> Vec<error::Error> {
+        let mut problems = Vec::new();
+
         let object_definitions = &self.object_definitions.object_definitions;
         let slots = &self.slots.slots;
         let draw_groups = &self.draw_groups.draw_groups;
@@ -119,36 +219,43 @@ impl IffDescription {
         let sprite_ids = sprites.iter().map(|x| x.chunk_id).collect::<std::collections::HashSet<_>>();
 
         for object_definition in object_definitions {
-            if object_definition.slot_chunk_id.as_i16() != 0 {
-                anyhow::ensure!(
-                    slot_ids.contains(&object_definition.slot_chunk_id),
-                    "failed to find slot {} used in object definition {} {}",
-                    object_definition.slot_chunk_id.as_i16(),
-                    object_definition.chunk_id.as_i16(),
-                    object_definition.chunk_label
-                );
+            if object_definition.slot_chunk_id.as_i16() != 0 && !slot_ids.contains(&object_definition.slot_chunk_id) {
+                problems.push(error::Error::MissingChunk {
+                    chunk_type: *b"SLOT",
+                    chunk_id: object_definition.slot_chunk_id,
+                    used_by_chunk_type: *b"OBJD",
+                    used_by_chunk_id: object_definition.chunk_id,
+                    used_by_chunk_label: object_definition.chunk_label.clone(),
+                    item_list_index: None,
+                });
             }
 
             if object_definition.subindex != -1 {
                 for i in 0..object_definition.draw_group_count {
                     let draw_group_chunk_id = object_definition.base_draw_group_chunk_id + i;
-                    anyhow::ensure!(
-                        draw_group_ids.contains(&draw_group_chunk_id),
-                        "failed to find draw group {} used in object definition {} {}",
-                        draw_group_chunk_id.as_i16(),
-                        object_definition.chunk_id.as_i16(),
-                        object_definition.chunk_label
-                    );
+                    if !draw_group_ids.contains(&draw_group_chunk_id) {
+                        problems.push(error::Error::MissingChunk {
+                            chunk_type: *b"DGRP",
+                            chunk_id: draw_group_chunk_id,
+                            used_by_chunk_type: *b"OBJD",
+                            used_by_chunk_id: object_definition.chunk_id,
+                            used_by_chunk_label: object_definition.chunk_label.clone(),
+                            item_list_index: None,
+                        });
+                    }
                 }
                 for i in 0..object_definition.dynamic_sprite_count {
                     let sprite_chunk_id = object_definition.base_dynamic_sprite_chunk_id + i;
-                    anyhow::ensure!(
-                        sprite_ids.contains(&sprite_chunk_id),
-                        "failed to find dynamic sprite {} used in object definition {} {}",
-                        sprite_chunk_id.as_i16(),
-                        object_definition.chunk_id.as_i16(),
-                        object_definition.chunk_label
-                    );
+                    if !sprite_ids.contains(&sprite_chunk_id) {
+                        problems.push(error::Error::MissingChunk {
+                            chunk_type: *b"SPR2",
+                            chunk_id: sprite_chunk_id,
+                            used_by_chunk_type: *b"OBJD",
+                            used_by_chunk_id: object_definition.chunk_id,
+                            used_by_chunk_label: object_definition.chunk_label.clone(),
+                            item_list_index: None,
+                        });
+                    }
                 }
             }
         }
@@ -157,31 +264,127 @@ impl IffDescription {
             for (i, draw_group_item_list) in draw_group.draw_group_item_lists.iter().enumerate() {
                 for draw_group_item in &draw_group_item_list.draw_group_items {
                     if let Some(sprite) = sprites.iter().find(|x| x.chunk_id == draw_group_item.sprite_chunk_id) {
-                        anyhow::ensure!(
-                            sprite.sprite_frame_count > draw_group_item.sprite_index.as_i32(),
-                            "failed to find frame {} of sprite {} used in draw group {} {} item list {}",
-                            draw_group_item.sprite_index.as_i32(),
-                            draw_group_item.sprite_chunk_id.as_i16(),
-                            draw_group.chunk_id.as_i16(),
-                            draw_group.chunk_label,
-                            i,
-                        );
+                        if sprite.sprite_frame_count <= draw_group_item.sprite_index.as_i32() {
+                            problems.push(error::Error::MissingSpriteFrame {
+                                chunk_type: *b"SPR2",
+                                chunk_id: draw_group_item.sprite_chunk_id,
+                                frame_index: draw_group_item.sprite_index.as_i32(),
+                                used_by_chunk_id: draw_group.chunk_id,
+                                used_by_chunk_label: draw_group.chunk_label.clone(),
+                                item_list_index: i,
+                            });
+                        }
                     } else {
-                        anyhow::bail!(
-                            "failed to find sprite {} used in draw group {} {} item list {}",
-                            draw_group_item.sprite_chunk_id.as_i16(),
-                            draw_group.chunk_id.as_i16(),
-                            draw_group.chunk_label,
-                            i,
-                        );
+                        problems.push(error::Error::MissingChunk {
+                            chunk_type: *b"SPR2",
+                            chunk_id: draw_group_item.sprite_chunk_id,
+                            used_by_chunk_type: *b"DGRP",
+                            used_by_chunk_id: draw_group.chunk_id,
+                            used_by_chunk_label: draw_group.chunk_label.clone(),
+                            item_list_index: Some(i),
+                        });
                     }
                 }
             }
         }
 
+        let mut behaviour_ids = std::collections::HashSet::new();
+        for behaviour in &self.behaviours.behaviours {
+            if !behaviour_ids.insert(behaviour.chunk_id) {
+                problems.push(error::Error::DuplicateChunkId {
+                    chunk_type: *b"BHAV",
+                    chunk_id: behaviour.chunk_id,
+                });
+            }
+        }
+
+        let mut string_table_ids = std::collections::HashSet::new();
+        for string_table in &self.string_tables.string_tables {
+            if !string_table_ids.insert(string_table.chunk_id) {
+                problems.push(error::Error::DuplicateChunkId {
+                    chunk_type: *b"STR#",
+                    chunk_id: string_table.chunk_id,
+                });
+            }
+        }
+
+        let mut catalog_strings_ids = std::collections::HashSet::new();
+        for catalog_strings in &self.catalog.catalog_strings {
+            if !catalog_strings_ids.insert(catalog_strings.chunk_id) {
+                problems.push(error::Error::DuplicateChunkId {
+                    chunk_type: *b"CTSS",
+                    chunk_id: catalog_strings.chunk_id,
+                });
+            }
+            let mut languages = std::collections::HashSet::new();
+            for language in &catalog_strings.languages {
+                if !languages.insert((language.language_code, &language.variant)) {
+                    problems.push(error::Error::DuplicateLanguage {
+                        chunk_id: catalog_strings.chunk_id,
+                        chunk_label: catalog_strings.chunk_label.clone(),
+                        language_code: language.language_code,
+                        variant: language.variant.clone(),
+                    });
+                }
+            }
+        }
+
+        problems
+    }
+
+    /// Checks that every chunk referenced by the description is part of it.
+    pub fn validate(self) -> anyhow::Result<IffDescription> {
+        if let Some(problem) = self.find_problems().into_iter().next() {
+            return Err(problem.into());
+        }
         Ok(self)
     }
 
+    pub fn assign_new_guids(
+        &mut self,
+        used_guids: &std::collections::HashSet<i32>,
+    ) -> std::collections::HashMap<i32, i32> {
+        let mut replaced_guids = std::collections::HashMap::new();
+        let mut used_guids = used_guids.clone();
+        used_guids.extend(self.object_definitions.object_definitions.iter().map(|x| x.guid));
+
+        for object_definition in &mut self.object_definitions.object_definitions {
+            let guid = guid::generate_guid(&used_guids);
+            used_guids.insert(guid);
+
+            // objects that are already clones keep pointing at the object they were cloned from
+            if object_definition.originalguid == 0 {
+                object_definition.originalguid = object_definition.guid;
+            }
+            replaced_guids.insert(object_definition.guid, guid);
+            object_definition.guid = guid;
+        }
+
+        replaced_guids
+    }
+
+    pub fn replace_guids_in_behaviours(
+        &self,
+        source_directory: &std::path::Path,
+        guids: &std::collections::HashMap<i32, i32>,
+    ) -> anyhow::Result<()> {
+        if guids.is_empty() {
+            return Ok(());
+        }
+        for behaviour in &self.behaviours.behaviours {
+            let listing_file_path = source_directory.join(&behaviour.file_path_relative);
+            let listing = std::fs::read_to_string(&listing_file_path)
+                .with_context(|| error::file_read_error(&listing_file_path))?;
+            let replaced_listing = bhav::replace_guids_in_listing(&listing, guids)
+                .with_context(|| format!("Failed to replace GUIDs in {}", listing_file_path.display()))?;
+            if replaced_listing != listing {
+                std::fs::write(&listing_file_path, replaced_listing)
+                    .with_context(|| error::file_write_error(&listing_file_path))?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn update_sprite_variants(&mut self, variant_original: &str, variant_new: &str) -> anyhow::Result<()> {
         let variant_original = " - ".to_owned() + variant_original + " - sprites";
         let variant_new = " - ".to_owned() + variant_new + " - sprites";
@@ -206,66 +409,82 @@ impl IffDescription {
         Ok(())
     }
 
-    pub fn update_sprite_positions(&mut self, source_directory: &std::path::Path) -> anyhow::Result<()> {
-        for sprite in &mut self.sprites.sprites {
-            if sprite.sprite_type == spr::SpriteType::Spr1 {
-                continue;
-            }
-            for frame in &mut sprite.sprite_frames {
-                frame.palette_chunk_id = sprite.palette_chunk_id;
-
-                let alpha_sprite_file_path = source_directory
-                    .join(frame.sprite_channel_file_path_relative(spr::SpriteChannelType::Alpha, sprite.chunk_id)?);
-                let sprite_frame_directory = alpha_sprite_file_path.parent().with_context(|| {
-                    format!(
-                        "Failed to get sprite frame directory from sprite file path {}",
-                        alpha_sprite_file_path.display()
-                    )
-                })?;
-
-                let sprite_description_file_path =
-                    sprite::sprite_description_file_path(sprite_frame_directory, frame.zoom_level, frame.rotation);
-
-                let sprite_image_description = if sprite_description_file_path.is_file() {
-                    sprite::read_sprite_image_description_file(&sprite_description_file_path)?
-                } else {
-                    let sprite_image = image::open(&alpha_sprite_file_path)
-                        .with_context(|| error::file_read_error(&alpha_sprite_file_path))?
-                        .to_luma8();
-                    let sprite_image_description = sprite::calculate_sprite_image_description(
-                        &sprite_image,
-                        frame.zoom_level,
-                        sprite.palette_chunk_id,
-                        frame.transparent_color_index,
-                    );
-                    frame.cropped_width = sprite_image_description.bounds.right - sprite_image_description.bounds.left;
-                    frame.cropped_height = sprite_image_description.bounds.bottom - sprite_image_description.bounds.top;
-                    continue;
-                };
-
-                sprite.palette_chunk_id = sprite_image_description.palette_id;
-                frame.palette_chunk_id = sprite_image_description.palette_id;
-                frame.transparent_color_index = sprite_image_description.transparent_color_index;
-
-                frame.bounds_left = sprite_image_description.bounds.left;
-                frame.bounds_top = sprite_image_description.bounds.top;
+    pub fn update_sprite_positions(
+        &mut self,
+        source_directory: &std::path::Path,
+        split_sprites: &sprite::SplitSprites,
+    ) -> anyhow::Result<()> {
+        for sprite_index in 0..self.sprites.sprites.len() {
+            self.update_sprite_position(sprite_index, source_directory, split_sprites)?;
+        }
+        Ok(())
+    }
+
+    /// Updates the bounds of the frames of one sprite and the offsets of the draw group items that use them.
+    pub fn update_sprite_position(
+        &mut self,
+        sprite_index: usize,
+        source_directory: &std::path::Path,
+        split_sprites: &sprite::SplitSprites,
+    ) -> anyhow::Result<()> {
+        let sprite = &mut self.sprites.sprites[sprite_index];
+        if sprite.sprite_type == spr::SpriteType::Spr1 {
+            return Ok(());
+        }
+        for frame in &mut sprite.sprite_frames {
+            frame.palette_chunk_id = sprite.palette_chunk_id;
+
+            let alpha_sprite_file_path = source_directory
+                .join(frame.sprite_channel_file_path_relative(spr::SpriteChannelType::Alpha, sprite.chunk_id)?);
+            let sprite_frame_directory = alpha_sprite_file_path.parent().with_context(|| {
+                format!(
+                    "Failed to get sprite frame directory from sprite file path {}",
+                    alpha_sprite_file_path.display()
+                )
+            })?;
+
+            let sprite_description_file_path =
+                sprite::sprite_description_file_path(sprite_frame_directory, frame.zoom_level, frame.rotation);
+
+            let sprite_image_description = if let Some(sprite_image_description) =
+                split_sprites.read_image_description(&sprite_description_file_path)?
+            {
+                sprite_image_description
+            } else {
+                let sprite_image = split_sprites.read_channel_image(&alpha_sprite_file_path)?;
+                let sprite_image_description = sprite::calculate_sprite_image_description(
+                    &sprite_image,
+                    frame.zoom_level,
+                    sprite.palette_chunk_id,
+                    frame.transparent_color_index,
+                );
                 frame.cropped_width = sprite_image_description.bounds.right - sprite_image_description.bounds.left;
                 frame.cropped_height = sprite_image_description.bounds.bottom - sprite_image_description.bounds.top;
+                continue;
+            };
+
+            sprite.palette_chunk_id = sprite_image_description.palette_id;
+            frame.palette_chunk_id = sprite_image_description.palette_id;
+            frame.transparent_color_index = sprite_image_description.transparent_color_index;
+
+            frame.bounds_left = sprite_image_description.bounds.left;
+            frame.bounds_top = sprite_image_description.bounds.top;
+            frame.cropped_width = sprite_image_description.bounds.right - sprite_image_description.bounds.left;
+            frame.cropped_height = sprite_image_description.bounds.bottom - sprite_image_description.bounds.top;
 
-                for draw_group in self.draw_groups.draw_groups.iter_mut() {
-                    for draw_group_item_list in &mut draw_group.draw_group_item_lists {
-                        for draw_group_item in &mut draw_group_item_list.draw_group_items {
-                            if sprite.chunk_id == draw_group_item.sprite_chunk_id
-                                && draw_group_item.sprite_index == frame.index
-                            {
-                                let offset_x = if draw_group_item.flags & 0b1 == 0 {
-                                    sprite_image_description.offsets.x
-                                } else {
-                                    sprite_image_description.offsets.x_flipped
-                                };
-                                draw_group_item.sprite_offset_x = offset_x;
-                                draw_group_item.sprite_offset_y = sprite_image_description.offsets.y;
-                            }
+            for draw_group in self.draw_groups.draw_groups.iter_mut() {
+                for draw_group_item_list in &mut draw_group.draw_group_item_lists {
+                    for draw_group_item in &mut draw_group_item_list.draw_group_items {
+                        if sprite.chunk_id == draw_group_item.sprite_chunk_id
+                            && draw_group_item.sprite_index == frame.index
+                        {
+                            let offset_x = if draw_group_item.flags & 0b1 == 0 {
+                                sprite_image_description.offsets.x
+                            } else {
+                                sprite_image_description.offsets.x_flipped
+                            };
+                            draw_group_item.sprite_offset_x = offset_x;
+                            draw_group_item.sprite_offset_y = sprite_image_description.offsets.y;
                         }
                     }
                 }
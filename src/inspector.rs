@@ -0,0 +1,73 @@
+use ts1_compiler::iff;
+use ts1_compiler::message;
+
+use anyhow::Context;
+
+#[derive(serde::Serialize)]
+struct ChunkInspection {
+    chunk_type: String,
+    id: i16,
+    flags: i16,
+    label: String,
+    size: u32,
+    address: u32,
+}
+
+#[derive(serde::Serialize)]
+struct IffInspection {
+    rsmp_address: u32,
+    chunks: Vec<ChunkInspection>,
+}
+
+fn inspect_iff(iff: &iff::Iff) -> IffInspection {
+    let chunks = iff
+        .chunks
+        .iter()
+        .zip(iff.chunk_addresses())
+        .map(|(chunk, address)| ChunkInspection {
+            chunk_type: String::from_utf8_lossy(chunk.header.chunk_type()).into_owned(),
+            id: chunk.header.id().as_i16(),
+            flags: chunk.header.flags(),
+            label: chunk.header.label(),
+            size: chunk.header.size(),
+            address,
+        })
+        .collect();
+
+    IffInspection {
+        rsmp_address: iff.rsmp_address(),
+        chunks,
+    }
+}
+
+fn format_iff_inspection(iff_inspection: &IffInspection) -> String {
+    let mut text = vec![
+        format!("rsmp address: {}", iff_inspection.rsmp_address),
+        format!(
+            "{:<4} {:>6} {:>6} {:>10} {:>10} label",
+            "type", "id", "flags", "size", "address"
+        ),
+    ];
+    for chunk in &iff_inspection.chunks {
+        text.push(format!(
+            "{:<4} {:>6} 0x{:04x} {:>10} {:>10} {}",
+            chunk.chunk_type, chunk.id, chunk.flags, chunk.size, chunk.address, chunk.label
+        ));
+    }
+    text.join("\n")
+}
+
+pub fn inspect(iff_file_path: &std::path::Path, json: bool) -> anyhow::Result<()> {
+    let iff = iff::read_iff_file(iff_file_path)?;
+    let iff_inspection = inspect_iff(&iff);
+
+    if json {
+        let json_string = serde_json::to_string_pretty(&iff_inspection)
+            .with_context(|| format!("Failed to serialize chunk table of {}", iff_file_path.display()))?;
+        println!("{json_string}");
+    } else {
+        message::data(&format_iff_inspection(&iff_inspection), &iff_inspection);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+//! Compiles and decompiles objects for The Sims 1.
+//!
+//! The `ts1-compiler` command line tool is a thin wrapper around this library. The main entry points are:
+//!
+//! - [`iff::read_iff_file`] and [`iff::write_iff_file`] read and write iff files and entries of far archives.
+//! - [`iff_description::IffDescription`] is the xml description of an object that gets compiled into an iff file.
+//! - The `to_chunk` functions build chunks, e.g. [`objd::ObjectDefinition::to_chunk`] and [`spr::Sprite::to_chunk`].
+//! - [`splitter::split`] splits rendered sprites into their channels and quantizes them with [`quantizer`].
+//! - [`iff::rebuild_iff_file`] replaces the chunks of an iff file with the chunks of a description.
+//! - [`compiler::compile`] and [`decompiler::decompile`] run the whole pipeline like the command line tool.
+//! - [`message`] prints progress, warnings and errors as text or as json lines.
+//! - [`error::Error`] is the typed error behind failures callers may want to handle, like a missing chunk.
+//!
+//! ```no_run
+//! let the_sims_directory = std::path::Path::new("C:/Program Files/Maxis/The Sims");
+//! let xml_file_path = std::path::Path::new("chair/chair.xml");
+//! ts1_compiler::compiler::compile(the_sims_directory, xml_file_path, Default::default())?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+/// Backups of iff files taken before they are overwritten.
+pub mod backup;
+/// Behaviour chunks and their text listings.
+pub mod bhav;
+/// Copies of an object with fresh GUIDs.
+pub mod cloner;
+/// Compiling descriptions into iff files.
+pub mod compiler;
+/// Catalog string chunks.
+pub mod ctss;
+/// Turning iff files back into descriptions and sprite images.
+pub mod decompiler;
+/// Draw group chunks.
+pub mod dgrp;
+/// Adding rotations to the draw groups of a description.
+pub mod draw_group_rotation_adder;
+/// The typed errors of the library.
+pub mod error;
+/// Far archives.
+pub mod far;
+pub(crate) mod guid;
+/// Finding objects that share a GUID.
+pub mod guid_scanner;
+/// Reading, writing and rebuilding iff files.
+pub mod iff;
+/// The xml description of an object.
+pub mod iff_description;
+/// Progress, warnings, errors and results as text or json.
+pub mod message;
+/// Object definition chunks.
+pub mod objd;
+/// Palette chunks.
+pub mod palt;
+/// Quantizing sprite channels to palettes.
+pub mod quantizer;
+/// Slot chunks.
+pub mod slot;
+/// Splitting rendered sprites into their channels.
+pub mod splitter;
+/// Sprite chunks.
+pub mod spr;
+/// Sprite channels, rotations and zoom levels.
+pub mod sprite;
+/// Exporting the sprites of an iff file to images.
+pub mod sprite_exporter;
+/// String table chunks.
+pub mod string_table;
+pub(crate) mod windows_1252;
+/// Updating a description with the sprites of a split.
+pub mod xml_updater;
@@ -0,0 +1,329 @@
+use ts1_compiler::error;
+use ts1_compiler::iff;
+use ts1_compiler::iff_description;
+use ts1_compiler::message;
+use ts1_compiler::palt;
+use ts1_compiler::spr;
+use ts1_compiler::sprite;
+
+use anyhow::Context;
+
+#[derive(Default)]
+struct Problems {
+    problems: Vec<(String, Option<message::ChunkReference>)>,
+}
+
+impl Problems {
+    fn add(&mut self, problem: impl std::fmt::Display, chunk: Option<&message::ChunkReference>) {
+        self.problems.push((format!("{problem:#}"), chunk.cloned()));
+    }
+
+    fn len(&self) -> usize {
+        self.problems.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+fn sprite_chunk_reference(sprite: &spr::Sprite) -> message::ChunkReference {
+    let chunk_type = match sprite.sprite_type {
+        spr::SpriteType::Spr1 => "SPR#",
+        spr::SpriteType::Spr2 => "SPR2",
+    };
+    message::ChunkReference::new(chunk_type, sprite.chunk_id, &sprite.chunk_label)
+}
+
+fn sprite_channels(sprite: &spr::Sprite) -> &'static [spr::SpriteChannelType] {
+    match sprite.sprite_type {
+        spr::SpriteType::Spr1 => &[spr::SpriteChannelType::Depth],
+        spr::SpriteType::Spr2 => &[
+            spr::SpriteChannelType::Color,
+            spr::SpriteChannelType::Depth,
+            spr::SpriteChannelType::Alpha,
+        ],
+    }
+}
+
+fn find_duplicate_ids<'a>(
+    chunk_type: &[u8; 4],
+    chunks: impl Iterator<Item = (iff::IffChunkId, &'a str)>,
+    problems: &mut Problems,
+) {
+    let mut chunk_ids = std::collections::HashSet::new();
+    for (chunk_id, chunk_label) in chunks {
+        if !chunk_ids.insert(chunk_id) {
+            let chunk_type_string = String::from_utf8_lossy(chunk_type);
+            problems.add(
+                error::Error::DuplicateChunkId {
+                    chunk_type: *chunk_type,
+                    chunk_id,
+                },
+                Some(&message::ChunkReference::new(&chunk_type_string, chunk_id, chunk_label)),
+            );
+        }
+    }
+}
+
+// the channel images of every frame have to exist, have the same size and be at least as large as their zoom level
+fn check_channel_images(
+    source_directory: &std::path::Path,
+    sprite: &spr::Sprite,
+    channel_dimensions: &mut std::collections::HashMap<std::path::PathBuf, (u32, u32)>,
+    problems: &mut Problems,
+) {
+    let chunk = sprite_chunk_reference(sprite);
+    for frame in &sprite.sprite_frames {
+        let frame_index = frame.index.as_i32();
+        let mut frame_dimensions = Vec::new();
+        for channel in sprite_channels(sprite) {
+            let file_path = match frame.sprite_channel_file_path_relative(*channel, sprite.chunk_id) {
+                Ok(x) => source_directory.join(x),
+                Err(error) => {
+                    problems.add(error, Some(&chunk));
+                    continue;
+                }
+            };
+            if !file_path.is_file() {
+                problems.add(
+                    format!(
+                        "Failed to find {} channel {} of frame {} in sprite {} {}",
+                        channel,
+                        file_path.display(),
+                        frame_index,
+                        sprite.chunk_id.as_i16(),
+                        sprite.chunk_label
+                    ),
+                    Some(&chunk),
+                );
+                continue;
+            }
+            match spr::read_bmp(&file_path) {
+                Ok(bmp) => {
+                    use image::ImageDecoder;
+                    let dimensions = bmp.dimensions();
+                    channel_dimensions.insert(file_path, dimensions);
+                    frame_dimensions.push((*channel, dimensions));
+                }
+                Err(error) => problems.add(error, Some(&chunk)),
+            }
+        }
+
+        if frame_dimensions.windows(2).any(|x| x[0].1 != x[1].1) {
+            let sizes: Vec<_> = frame_dimensions
+                .iter()
+                .map(|(channel, (width, height))| format!("{channel} {width}x{height}"))
+                .collect();
+            problems.add(
+                format!(
+                    "Channel images of frame {} in sprite {} {} differ in size: {}",
+                    frame_index,
+                    sprite.chunk_id.as_i16(),
+                    sprite.chunk_label,
+                    sizes.join(", ")
+                ),
+                Some(&chunk),
+            );
+        }
+
+        // spr1 sprites are not rendered by zoom level
+        if sprite.sprite_type == spr::SpriteType::Spr1 {
+            continue;
+        }
+        let (zoom_width, zoom_height) = sprite::sprite_dimensions(frame.zoom_level);
+        let (zoom_width, zoom_height) = (u32::try_from(zoom_width).unwrap(), u32::try_from(zoom_height).unwrap());
+        for (channel, (width, height)) in &frame_dimensions {
+            if *width < zoom_width || *height < zoom_height {
+                problems.add(
+                    format!(
+                        "Frame {} of sprite {} {} has a {}x{} {} channel which is smaller than the {}x{} of zoom \
+                        level {}",
+                        frame_index,
+                        sprite.chunk_id.as_i16(),
+                        sprite.chunk_label,
+                        width,
+                        height,
+                        channel,
+                        zoom_width,
+                        zoom_height,
+                        frame.zoom_level
+                    ),
+                    Some(&chunk),
+                );
+            }
+        }
+    }
+}
+
+// the rectangles the compiler reads from the channel images have to lie inside of them, and every row has to fit into
+// the row commands of an spr2 chunk
+fn check_sprite_encoding(
+    source_directory: &std::path::Path,
+    sprite: &spr::Sprite,
+    channel_dimensions: &std::collections::HashMap<std::path::PathBuf, (u32, u32)>,
+    problems: &mut Problems,
+) {
+    let chunk = sprite_chunk_reference(sprite);
+    let problem_count = problems.len();
+    for frame in &sprite.sprite_frames {
+        let (Ok(x), Ok(y)) = (u32::try_from(frame.bounds_left), u32::try_from(frame.bounds_top)) else {
+            problems.add(
+                format!(
+                    "Frame {} of sprite {} {} starts at {},{} which is outside of its channel images",
+                    frame.index.as_i32(),
+                    sprite.chunk_id.as_i16(),
+                    sprite.chunk_label,
+                    frame.bounds_left,
+                    frame.bounds_top
+                ),
+                Some(&chunk),
+            );
+            continue;
+        };
+        let width = std::cmp::max(u32::try_from(frame.cropped_width).unwrap_or(0), 1);
+        let height = std::cmp::max(u32::try_from(frame.cropped_height).unwrap_or(0), 1);
+        for channel in sprite_channels(sprite) {
+            let Ok(file_path_relative) = frame.sprite_channel_file_path_relative(*channel, sprite.chunk_id) else {
+                continue;
+            };
+            let file_path = source_directory.join(file_path_relative);
+            if let Some(dimensions) = channel_dimensions.get(&file_path)
+                && let Err(error) = spr::check_rect_bounds(&file_path, (x, y, width, height), *dimensions)
+            {
+                problems.add(error, Some(&chunk));
+            }
+        }
+    }
+
+    // encoding the sprite is only safe once its frames are known to be inside of their images
+    if problems.len() == problem_count
+        && let Err(error) = sprite.to_chunk(source_directory, &sprite::SplitSprites::default())
+    {
+        problems.add(error, Some(&chunk));
+    }
+}
+
+// all sprites that share a palette ID get the palette of the first one
+fn check_palettes(source_directory: &std::path::Path, sprites: &[spr::Sprite], problems: &mut Problems) {
+    let mut palettes = std::collections::HashMap::new();
+    for sprite in sprites.iter().filter(|x| x.sprite_type == spr::SpriteType::Spr2) {
+        let chunk = sprite_chunk_reference(sprite);
+        for frame in &sprite.sprite_frames {
+            let Ok(file_path_relative) =
+                frame.sprite_channel_file_path_relative(spr::SpriteChannelType::Color, sprite.chunk_id)
+            else {
+                continue;
+            };
+            let file_path = source_directory.join(file_path_relative);
+            if !file_path.is_file() {
+                continue;
+            }
+            let palette = match palt::read_bmp_palette(&file_path) {
+                Ok(x) => x,
+                Err(error) => {
+                    problems.add(error, Some(&chunk));
+                    continue;
+                }
+            };
+            match palettes.entry(sprite.palette_chunk_id) {
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    entry.insert((palette, file_path));
+                }
+                std::collections::hash_map::Entry::Occupied(entry) => {
+                    let (first_palette, first_file_path) = entry.get();
+                    if *first_palette != palette {
+                        problems.add(
+                            format!(
+                                "Palette of {} differs from the palette of {} although both use palette {}",
+                                file_path.display(),
+                                first_file_path.display(),
+                                sprite.palette_chunk_id.as_i16()
+                            ),
+                            Some(&chunk),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Checks a description and its sprite images for everything that would make compiling it fail or produce a broken
+/// object, and reports all problems instead of stopping at the first one.
+pub fn lint(xml_file_path: &std::path::Path) -> anyhow::Result<()> {
+    let mut iff_description = iff_description::IffDescription::open(xml_file_path)
+        .with_context(|| format!("Failed to open xml file {}", xml_file_path.display()))?;
+    let source_directory = xml_file_path.parent().with_context(|| {
+        format!(
+            "Failed to get source directory from xml file path {}",
+            xml_file_path.display()
+        )
+    })?;
+
+    let mut problems = Problems::default();
+    for problem in iff_description.find_problems() {
+        problems.add(problem, None);
+    }
+
+    let sprites = &iff_description.sprites.sprites;
+    // SPR# and SPR2 chunks are different chunk types so they may share IDs
+    for (chunk_type, sprite_type) in [(b"SPR#", spr::SpriteType::Spr1), (b"SPR2", spr::SpriteType::Spr2)] {
+        find_duplicate_ids(
+            chunk_type,
+            sprites
+                .iter()
+                .filter(|x| x.sprite_type == sprite_type)
+                .map(|x| (x.chunk_id, x.chunk_label.as_str())),
+            &mut problems,
+        );
+    }
+    let draw_groups = &iff_description.draw_groups.draw_groups;
+    find_duplicate_ids(
+        b"DGRP",
+        draw_groups.iter().map(|x| (x.chunk_id, x.chunk_label.as_str())),
+        &mut problems,
+    );
+    let slots = &iff_description.slots.slots;
+    find_duplicate_ids(
+        b"SLOT",
+        slots.iter().map(|x| (x.chunk_id, x.chunk_label.as_str())),
+        &mut problems,
+    );
+
+    let mut channel_dimensions = std::collections::HashMap::new();
+    let mut readable_sprites = Vec::new();
+    for (sprite_index, sprite) in sprites.iter().enumerate() {
+        let problem_count = problems.len();
+        check_channel_images(source_directory, sprite, &mut channel_dimensions, &mut problems);
+        if problems.len() == problem_count {
+            readable_sprites.push(sprite_index);
+        }
+    }
+
+    // the sprite positions come from the channel images so they are only checked for sprites whose images were read
+    for sprite_index in readable_sprites {
+        let result =
+            iff_description.update_sprite_position(sprite_index, source_directory, &sprite::SplitSprites::default());
+        let sprite = &iff_description.sprites.sprites[sprite_index];
+        match result {
+            Ok(()) => check_sprite_encoding(source_directory, sprite, &channel_dimensions, &mut problems),
+            Err(error) => problems.add(error, Some(&sprite_chunk_reference(sprite))),
+        }
+    }
+
+    check_palettes(source_directory, &iff_description.sprites.sprites, &mut problems);
+
+    for (problem, chunk) in &problems.problems {
+        message::warning(problem, chunk.as_ref());
+    }
+    anyhow::ensure!(
+        problems.is_empty(),
+        "{} problems found in {}",
+        problems.len(),
+        xml_file_path.display()
+    );
+    message::info(&format!("No problems found in {}", xml_file_path.display()));
+
+    Ok(())
+}
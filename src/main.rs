@@ -1,23 +1,29 @@
-mod compiler;
-mod dgrp;
-mod draw_group_rotation_adder;
-mod error;
-mod iff;
-mod iff_description;
-mod objd;
-mod palt;
-mod quantizer;
-mod slot;
-mod splitter;
-mod spr;
-mod sprite;
-mod xml_updater;
+mod differ;
+mod inspector;
+mod linter;
+mod watcher;
+
+use ts1_compiler::backup;
+use ts1_compiler::bhav;
+use ts1_compiler::cloner;
+use ts1_compiler::compiler;
+use ts1_compiler::decompiler;
+use ts1_compiler::draw_group_rotation_adder;
+use ts1_compiler::far;
+use ts1_compiler::guid_scanner;
+use ts1_compiler::iff;
+use ts1_compiler::message;
+use ts1_compiler::splitter;
+use ts1_compiler::sprite_exporter;
+use ts1_compiler::xml_updater;
 
 #[derive(clap::Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: CliCommands,
+    #[arg(long, global = true, value_enum, default_value_t)]
+    message_format: message::MessageFormat,
 }
 
 #[derive(clap::Subcommand)]
@@ -27,6 +33,8 @@ enum CliCommands {
         object_name: String,
         #[arg(short, long)]
         variant: Option<String>,
+        #[arg(short, long, default_value_t = splitter::default_job_count())]
+        jobs: usize,
     },
     UpdateXml {
         source_directory: std::path::PathBuf,
@@ -37,6 +45,14 @@ enum CliCommands {
     Compile {
         the_sims_directory: std::path::PathBuf,
         xml_file_path: std::path::PathBuf,
+        #[arg(long)]
+        new_guids: bool,
+        #[arg(long)]
+        preserve_order: bool,
+        #[arg(long, default_value_t = backup::DEFAULT_BACKUP_COUNT)]
+        backups: usize,
+        #[arg(long)]
+        dry_run: bool,
     },
     CompileAdvanced {
         the_sims_directory: std::path::PathBuf,
@@ -47,23 +63,137 @@ enum CliCommands {
         #[arg(requires_all=["variant_new"])]
         variant_original: Option<String>,
         variant_new: Option<String>,
+        #[arg(long)]
+        new_guids: bool,
+        #[arg(long)]
+        preserve_order: bool,
+        #[arg(long, default_value_t = backup::DEFAULT_BACKUP_COUNT)]
+        backups: usize,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    Build {
+        the_sims_directory: std::path::PathBuf,
+        source_directory: std::path::PathBuf,
+        object_name: String,
+        #[arg(short, long, default_value_t = splitter::default_job_count())]
+        jobs: usize,
+        #[arg(long)]
+        write_intermediate_files: bool,
+        #[arg(long)]
+        preserve_order: bool,
+        #[arg(long, default_value_t = backup::DEFAULT_BACKUP_COUNT)]
+        backups: usize,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    Watch {
+        the_sims_directory: std::path::PathBuf,
+        source_directory: std::path::PathBuf,
+        object_name: String,
+        #[arg(short, long)]
+        variant: Option<String>,
+        #[arg(short, long, default_value_t = splitter::default_job_count())]
+        jobs: usize,
+        #[arg(long)]
+        preserve_order: bool,
+        #[arg(long, default_value_t = backup::DEFAULT_BACKUP_COUNT)]
+        backups: usize,
     },
     AddRotations {
         xml_file_path: std::path::PathBuf,
     },
+    Lint {
+        xml_file_path: std::path::PathBuf,
+    },
+    Decompile {
+        the_sims_directory: std::path::PathBuf,
+        iff_file_path: std::path::PathBuf,
+        xml_file_path: std::path::PathBuf,
+    },
+    ExportSprites {
+        iff_file_path: std::path::PathBuf,
+        output_directory: std::path::PathBuf,
+    },
+    Inspect {
+        iff_file_path: std::path::PathBuf,
+        #[arg(long)]
+        json: bool,
+    },
+    Diff {
+        old_iff_file_path: std::path::PathBuf,
+        new_iff_file_path: std::path::PathBuf,
+    },
+    ListFar {
+        far_file_path: std::path::PathBuf,
+    },
+    Pack {
+        far_file_path: std::path::PathBuf,
+        #[arg(required = true)]
+        iff_file_paths: Vec<std::path::PathBuf>,
+    },
+    Unpack {
+        far_file_path: std::path::PathBuf,
+        output_directory: std::path::PathBuf,
+    },
+    Disassemble {
+        iff_file_path: std::path::PathBuf,
+        #[arg(short, long)]
+        id: Option<i16>,
+    },
+    Clone {
+        the_sims_directory: std::path::PathBuf,
+        source_iff_file_path: std::path::PathBuf,
+        format_string: String,
+        creator_name: String,
+        object_name: String,
+        variant_name: Option<String>,
+    },
+    Restore {
+        iff_file_path: std::path::PathBuf,
+        backup_name: Option<String>,
+        #[arg(long, default_value_t = backup::DEFAULT_BACKUP_COUNT)]
+        backups: usize,
+    },
+    ScanGuids {
+        the_sims_directory: std::path::PathBuf,
+        #[arg(long)]
+        game_objects: bool,
+        #[arg(long)]
+        xml_file_path: Option<std::path::PathBuf>,
+        #[arg(long, requires_all = ["xml_file_path", "creator_name"])]
+        format_string: Option<String>,
+        #[arg(long, requires = "format_string")]
+        creator_name: Option<String>,
+        #[arg(long, requires = "format_string")]
+        variant: Option<String>,
+    },
 }
 
 fn main() -> anyhow::Result<()> {
     use clap::Parser;
     let cli = Cli::parse();
 
-    match &cli.command {
+    message::set_message_format(cli.message_format);
+    let result = run(&cli.command);
+    if let Err(error) = &result
+        && cli.message_format == message::MessageFormat::Json
+    {
+        message::error(error);
+        std::process::exit(1);
+    }
+    result
+}
+
+fn run(command: &CliCommands) -> anyhow::Result<()> {
+    match command {
         CliCommands::Split {
             source_directory,
             object_name,
             variant,
+            jobs,
         } => {
-            splitter::split(source_directory, object_name, variant.as_deref())?;
+            splitter::split(source_directory, object_name, variant.as_deref(), *jobs)?;
         }
         CliCommands::UpdateXml {
             source_directory,
@@ -75,8 +205,21 @@ fn main() -> anyhow::Result<()> {
         CliCommands::Compile {
             the_sims_directory,
             xml_file_path,
+            new_guids,
+            preserve_order,
+            backups,
+            dry_run,
         } => {
-            compiler::compile(the_sims_directory, xml_file_path)?;
+            compiler::compile(
+                the_sims_directory,
+                xml_file_path,
+                iff::RebuildOptions {
+                    new_guids: *new_guids,
+                    preserve_order: *preserve_order,
+                    backup_count: *backups,
+                    dry_run: *dry_run,
+                },
+            )?;
         }
         CliCommands::CompileAdvanced {
             the_sims_directory,
@@ -86,6 +229,10 @@ fn main() -> anyhow::Result<()> {
             object_name,
             variant_original,
             variant_new,
+            new_guids,
+            preserve_order,
+            backups,
+            dry_run,
         } => {
             compiler::compile_advanced(
                 the_sims_directory,
@@ -94,11 +241,150 @@ fn main() -> anyhow::Result<()> {
                 creator_name,
                 object_name,
                 variant_original.as_deref().zip(variant_new.as_deref()),
+                iff::RebuildOptions {
+                    new_guids: *new_guids,
+                    preserve_order: *preserve_order,
+                    backup_count: *backups,
+                    dry_run: *dry_run,
+                },
+            )?;
+        }
+        CliCommands::Build {
+            the_sims_directory,
+            source_directory,
+            object_name,
+            jobs,
+            write_intermediate_files,
+            preserve_order,
+            backups,
+            dry_run,
+        } => {
+            compiler::build(
+                the_sims_directory,
+                source_directory,
+                object_name,
+                *jobs,
+                *write_intermediate_files,
+                iff::RebuildOptions {
+                    preserve_order: *preserve_order,
+                    backup_count: *backups,
+                    dry_run: *dry_run,
+                    ..Default::default()
+                },
+            )?;
+        }
+        CliCommands::Watch {
+            the_sims_directory,
+            source_directory,
+            object_name,
+            variant,
+            jobs,
+            preserve_order,
+            backups,
+        } => {
+            watcher::watch(
+                the_sims_directory,
+                source_directory,
+                object_name,
+                variant.as_deref(),
+                *jobs,
+                iff::RebuildOptions {
+                    preserve_order: *preserve_order,
+                    backup_count: *backups,
+                    ..Default::default()
+                },
             )?;
         }
         CliCommands::AddRotations { xml_file_path } => {
             draw_group_rotation_adder::add_rotations(xml_file_path)?;
         }
+        CliCommands::Lint { xml_file_path } => {
+            linter::lint(xml_file_path)?;
+        }
+        CliCommands::Decompile {
+            the_sims_directory,
+            iff_file_path,
+            xml_file_path,
+        } => {
+            decompiler::decompile(the_sims_directory, iff_file_path, xml_file_path)?;
+        }
+        CliCommands::ExportSprites {
+            iff_file_path,
+            output_directory,
+        } => {
+            sprite_exporter::export_sprites(iff_file_path, output_directory)?;
+        }
+        CliCommands::Inspect { iff_file_path, json } => {
+            inspector::inspect(iff_file_path, *json)?;
+        }
+        CliCommands::Diff {
+            old_iff_file_path,
+            new_iff_file_path,
+        } => {
+            differ::diff(old_iff_file_path, new_iff_file_path)?;
+        }
+        CliCommands::ListFar { far_file_path } => {
+            far::list(far_file_path)?;
+        }
+        CliCommands::Pack {
+            far_file_path,
+            iff_file_paths,
+        } => {
+            far::pack(far_file_path, iff_file_paths)?;
+        }
+        CliCommands::Unpack {
+            far_file_path,
+            output_directory,
+        } => {
+            far::unpack(far_file_path, output_directory)?;
+        }
+        CliCommands::Disassemble { iff_file_path, id } => {
+            bhav::disassemble(iff_file_path, *id)?;
+        }
+        CliCommands::Clone {
+            the_sims_directory,
+            source_iff_file_path,
+            format_string,
+            creator_name,
+            object_name,
+            variant_name,
+        } => {
+            cloner::clone(
+                the_sims_directory,
+                source_iff_file_path,
+                format_string,
+                creator_name,
+                object_name,
+                variant_name.as_deref(),
+            )?;
+        }
+        CliCommands::Restore {
+            iff_file_path,
+            backup_name,
+            backups,
+        } => {
+            backup::restore(iff_file_path, backup_name.as_deref(), *backups)?;
+        }
+        CliCommands::ScanGuids {
+            the_sims_directory,
+            game_objects,
+            xml_file_path,
+            format_string,
+            creator_name,
+            variant,
+        } => {
+            let compiled_names = format_string.as_deref().map(|format_string| guid_scanner::CompiledNames {
+                format_string,
+                creator_name: creator_name.as_deref().unwrap_or_default(),
+                variant_name: variant.as_deref(),
+            });
+            guid_scanner::scan_guids(
+                the_sims_directory,
+                *game_objects,
+                xml_file_path.as_deref(),
+                compiled_names,
+            )?;
+        }
     }
     Ok(())
 }
@@ -0,0 +1,198 @@
+use crate::error;
+use crate::iff;
+use crate::sprite;
+
+static JSON_OUTPUT: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// How progress, results, warnings and errors are printed. With `Json` every message is one json object on its own line
+/// with a `type` of `progress`, `info`, `data`, `warning` or `error`, for programs like the Blender add-on that drive
+/// the compiler.
+#[derive(Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MessageFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+pub fn set_message_format(message_format: MessageFormat) {
+    JSON_OUTPUT.store(
+        message_format == MessageFormat::Json,
+        std::sync::atomic::Ordering::Relaxed,
+    );
+}
+
+fn is_json_output() -> bool {
+    JSON_OUTPUT.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+#[derive(Copy, Clone, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Stage {
+    Split,
+    Compile,
+}
+
+/// The chunk a message is about.
+#[derive(Clone, serde::Serialize)]
+pub struct ChunkReference {
+    pub chunk_type: String,
+    pub chunk_id: i16,
+    pub chunk_label: String,
+}
+
+impl ChunkReference {
+    pub fn new(chunk_type: &str, chunk_id: iff::IffChunkId, chunk_label: &str) -> ChunkReference {
+        ChunkReference {
+            chunk_type: chunk_type.to_owned(),
+            chunk_id: chunk_id.as_i16(),
+            chunk_label: chunk_label.to_owned(),
+        }
+    }
+}
+
+/// What a progress event is working on. Rotations and zoom levels use the names of the sprite files.
+#[derive(Clone, Default, serde::Serialize)]
+pub struct ProgressLocation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zoom: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tile: Option<[i32; 2]>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub chunk: Option<ChunkReference>,
+}
+
+impl ProgressLocation {
+    pub fn sprite(frame_name: &str, rotation: sprite::Rotation, zoom_level: sprite::ZoomLevel) -> ProgressLocation {
+        ProgressLocation {
+            frame: Some(frame_name.to_owned()),
+            rotation: Some(rotation.to_string()),
+            zoom: Some(zoom_level.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message<'a> {
+    Progress {
+        stage: Stage,
+        current: usize,
+        total: usize,
+        #[serde(flatten)]
+        location: &'a ProgressLocation,
+    },
+    Info {
+        message: &'a str,
+    },
+    Data {
+        data: serde_json::Value,
+    },
+    Warning {
+        message: &'a str,
+        #[serde(flatten, skip_serializing_if = "Option::is_none")]
+        chunk: Option<&'a ChunkReference>,
+    },
+    Error {
+        code: &'static str,
+        message: String,
+        causes: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        chunk_id: Option<i16>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+    },
+}
+
+fn print_json(message: &Message) {
+    // the messages only hold strings and numbers so serializing them can not fail
+    println!("{}", serde_json::to_string(message).unwrap());
+}
+
+/// Reports that `current` of `total` steps of a stage are done. Progress is only printed as json.
+pub fn progress(stage: Stage, current: usize, total: usize, location: &ProgressLocation) {
+    if is_json_output() {
+        print_json(&Message::Progress {
+            stage,
+            current,
+            total,
+            location,
+        });
+    }
+}
+
+pub fn info(message: &str) {
+    if is_json_output() {
+        print_json(&Message::Info { message });
+    } else {
+        println!("{message}");
+    }
+}
+
+/// Prints the result of a command, as `text` or with json as a `data` message holding `data`.
+pub fn data(text: &str, data: &impl serde::Serialize) {
+    if is_json_output() {
+        // the results only hold strings and numbers so converting them can not fail
+        let data = serde_json::to_value(data).unwrap();
+        print_json(&Message::Data { data });
+    } else {
+        println!("{text}");
+    }
+}
+
+pub fn warning(message: &str, chunk: Option<&ChunkReference>) {
+    if is_json_output() {
+        print_json(&Message::Warning { message, chunk });
+    } else {
+        println!("{message}");
+    }
+}
+
+/// Prints an error that stopped a command. Only used for json, the text format leaves errors to the caller.
+pub fn error(error: &anyhow::Error) {
+    let typed_error = error.downcast_ref::<error::Error>();
+    print_json(&Message::Error {
+        code: error_code(error),
+        message: error.to_string(),
+        causes: error.chain().skip(1).map(|x| x.to_string()).collect(),
+        chunk_id: typed_error.and_then(|x| x.chunk_id()).map(|x| x.as_i16()),
+        path: typed_error.and_then(|x| x.path()).map(|x| x.display().to_string()),
+    });
+}
+
+/// Prints an error that stopped one rebuild of a watched object but not the watch itself.
+pub fn rebuild_error(object_name: &str, rebuild_error: &anyhow::Error) {
+    if is_json_output() {
+        error(rebuild_error);
+    } else {
+        println!("Failed to rebuild {}: {:#}", object_name, rebuild_error);
+    }
+}
+
+/// A stable code for the kind of an error: `xml`, `json`, `iff`, `image`, the code of an [`error::Error`], `io` or
+/// `other`.
+pub fn error_code(error: &anyhow::Error) -> &'static str {
+    // a file that can be read but not parsed is reported by its format rather than as a read error
+    if error.downcast_ref::<quick_xml::DeError>().is_some() || error.downcast_ref::<quick_xml::Error>().is_some() {
+        "xml"
+    } else if error.downcast_ref::<serde_json::Error>().is_some() {
+        "json"
+    } else if error.downcast_ref::<binrw::Error>().is_some() {
+        "iff"
+    } else if error
+        .downcast_ref::<image::ImageError>()
+        .is_some_and(|x| !matches!(x, image::ImageError::IoError(_)))
+    {
+        "image"
+    } else if let Some(error) = error.downcast_ref::<error::Error>() {
+        error.code()
+    } else if error.downcast_ref::<std::io::Error>().is_some() {
+        "io"
+    } else {
+        "other"
+    }
+}
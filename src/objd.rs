@@ -1,6 +1,10 @@
+use crate::error;
 use crate::iff;
 
 pub const OBJD_CHUNK_DATA_SIZE: usize = 216;
+const OBJD_GUID_OFFSET: usize = 28;
+const OBJD_ORIGINAL_GUID_OFFSET: usize = 140;
+const OBJD_VERSION: u32 = 138;
 
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(deny_unknown_fields)]
@@ -217,13 +221,168 @@ pub struct ObjectDefinition {
     pub unused13: i16,
 }
 
+pub fn replace_guid_in_chunk(chunk: &mut iff::IffChunk, guid: i32) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        chunk.data.len() >= OBJD_ORIGINAL_GUID_OFFSET + 4,
+        "Object definition {} {} is too old to have an original GUID",
+        chunk.header.id().as_i16(),
+        chunk.header.label()
+    );
+    let read_guid = |data: &[u8], offset: usize| i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+
+    // objects that are already clones keep pointing at the object they were cloned from
+    if read_guid(&chunk.data, OBJD_ORIGINAL_GUID_OFFSET) == 0 {
+        let original_guid = read_guid(&chunk.data, OBJD_GUID_OFFSET);
+        chunk.data[OBJD_ORIGINAL_GUID_OFFSET..OBJD_ORIGINAL_GUID_OFFSET + 4]
+            .copy_from_slice(&original_guid.to_le_bytes());
+    }
+    chunk.data[OBJD_GUID_OFFSET..OBJD_GUID_OFFSET + 4].copy_from_slice(&guid.to_le_bytes());
+    Ok(())
+}
+
 impl ObjectDefinition {
+    pub fn custom_wall_style_sprite_id(&self) -> Option<iff::IffChunkId> {
+        (self.customwallstyleid != 0).then(|| iff::IffChunkId::new(self.customwallstyleid))
+    }
+
+    pub fn from_chunk(chunk: &iff::IffChunk) -> anyhow::Result<ObjectDefinition> {
+        // only the latest version is written back, so older versions would be changed by a round trip
+        let version = iff::IffChunkReader::new(&chunk.data).read_u32()?;
+        anyhow::ensure!(
+            version == OBJD_VERSION,
+            error::Error::UnsupportedVersion {
+                chunk_type: *chunk.header.chunk_type(),
+                chunk_id: chunk.header.id(),
+                chunk_label: chunk.header.label(),
+                version,
+            }
+        );
+
+        // fields missing at the end of the chunk are read as 0
+        let mut objd_data = chunk.data.clone();
+        if objd_data.len() < OBJD_CHUNK_DATA_SIZE {
+            objd_data.resize(OBJD_CHUNK_DATA_SIZE, 0);
+        }
+
+        let mut reader = iff::IffChunkReader::new(&objd_data);
+        reader.read_u32()?;
+
+        Ok(ObjectDefinition {
+            chunk_label: chunk.header.label(),
+            chunk_id: chunk.header.id(),
+            version: Version::V138,
+            initialstacksize: reader.read_i16()?,
+            base_draw_group_chunk_id: iff::IffChunkId::new(reader.read_i16()?),
+            draw_group_count: reader.read_i16()?,
+            maintreeid: reader.read_i16()?,
+            gardeningtreeid: reader.read_i16()?,
+            treetableid: reader.read_i16()?,
+            interactiongroup: reader.read_i16()?,
+            object_type: reader.read_i16()?,
+            masterid: reader.read_i16()?,
+            subindex: reader.read_i16()?,
+            washhandstreeid: reader.read_i16()?,
+            animtableid: reader.read_i16()?,
+            guid: reader.read_i32()?,
+            disabled: reader.read_i16()?,
+            portaltreeid: reader.read_i16()?,
+            price: reader.read_i16()?,
+            bodystringsid: reader.read_i16()?,
+            slot_chunk_id: iff::IffChunkId::new(reader.read_i16()?),
+            allowintersectiontreeid: reader.read_i16()?,
+            usesfntable: reader.read_i16()?,
+            unused4: reader.read_i16()?,
+            preptreeid: reader.read_i16()?,
+            cooktreeid: reader.read_i16()?,
+            surfacetreeid: reader.read_i16()?,
+            disposetreeid: reader.read_i16()?,
+            foodtreeid: reader.read_i16()?,
+            pickupfromslottreeid: reader.read_i16()?,
+            washdishtreeid: reader.read_i16()?,
+            eatingsurfacetreeid: reader.read_i16()?,
+            sittreeid: reader.read_i16()?,
+            standtreeid: reader.read_i16()?,
+            saleprice: reader.read_i16()?,
+            initialdepreciation: reader.read_i16()?,
+            dailydepreciation: reader.read_i16()?,
+            selfdepreciating: reader.read_i16()?,
+            depreciationlimit: reader.read_i16()?,
+            roomflags: reader.read_i16()?,
+            functionflags: reader.read_i16()?,
+            catalogid: reader.read_i16()?,
+            globalsimulationobject: reader.read_i16()?,
+            inittreeid: reader.read_i16()?,
+            placementtreeid: reader.read_i16()?,
+            userpickuptreeid: reader.read_i16()?,
+            wallstyle: reader.read_i16()?,
+            loadtreeid: reader.read_i16()?,
+            userplacementtreeid: reader.read_i16()?,
+            objectversion: reader.read_i16()?,
+            roomchangedtreeid: reader.read_i16()?,
+            motiveeffectsid: reader.read_i16()?,
+            cleanuptreeid: reader.read_i16()?,
+            levelinforequesttreeid: reader.read_i16()?,
+            catalogpopupid: reader.read_i16()?,
+            servingsurfacetreeid: reader.read_i16()?,
+            leveloffset: reader.read_i16()?,
+            shadow: reader.read_i16()?,
+            numattributes: reader.read_i16()?,
+            cleantreeid: reader.read_i16()?,
+            queueskippedtreeid: reader.read_i16()?,
+            frontfacedirection: reader.read_i16()?,
+            walladjacencychangedtreeid: reader.read_i16()?,
+            leadobject: reader.read_i16()?,
+            base_dynamic_sprite_chunk_id: iff::IffChunkId::new(reader.read_i16()?),
+            dynamic_sprite_count: reader.read_i16()?,
+            chairentryflags: reader.read_i16()?,
+            tilewidth: reader.read_i16()?,
+            suitnotcopyable: reader.read_i16()?,
+            buildmodetype: reader.read_i16()?,
+            originalguid: reader.read_i32()?,
+            originalsuitguid: reader.read_i32()?,
+            pickuptreeid: reader.read_i16()?,
+            thumbnailgraphicindex: reader.read_i16()?,
+            shadowflags: reader.read_i16()?,
+            footprintinsetmask: reader.read_i16()?,
+            mtadjupdatetreeid: reader.read_i16()?,
+            shadowbrightness: reader.read_i16()?,
+            repairtreeid: reader.read_i16()?,
+            customwallstyleid: reader.read_i16()?,
+            ratinghunger: reader.read_i16()?,
+            ratingcomfort: reader.read_i16()?,
+            ratinghygiene: reader.read_i16()?,
+            ratingbladder: reader.read_i16()?,
+            ratingenergy: reader.read_i16()?,
+            ratingfun: reader.read_i16()?,
+            ratingroom: reader.read_i16()?,
+            ratingskillflags: reader.read_i16()?,
+            numtypeattributes: reader.read_i16()?,
+            miscflags: reader.read_i16()?,
+            typeattrguid: reader.read_i32()?,
+            functionsubsort: reader.read_i16()?,
+            downtownsort: reader.read_i16()?,
+            keepbuying: reader.read_i16()?,
+            vacationsort: reader.read_i16()?,
+            resetlotaction: reader.read_i16()?,
+            communitysort: reader.read_i16()?,
+            dreamflags: reader.read_i16()?,
+            renderflags: reader.read_i16()?,
+            unused8: reader.read_i16()?,
+            unused9: reader.read_i16()?,
+            unused10: reader.read_i16()?,
+            unused11: reader.read_i16()?,
+            unused12: reader.read_i16()?,
+            unused13: reader.read_i16()?,
+        })
+    }
+
+    /// Builds the OBJD chunk, optionally with a different GUID than the one in the description.
     pub fn to_chunk(&self, replacement_guid: Option<i32>) -> anyhow::Result<iff::IffChunk> {
         let objd_chunk_header =
             iff::IffChunkHeader::new(b"OBJD", OBJD_CHUNK_DATA_SIZE, self.chunk_id, &self.chunk_label)?;
         let mut objd_data = Vec::with_capacity(iff::IFF_CHUNK_HEADER_SIZE + OBJD_CHUNK_DATA_SIZE);
 
-        objd_data.extend_from_slice(&138i32.to_le_bytes());
+        objd_data.extend_from_slice(&OBJD_VERSION.to_le_bytes());
         objd_data.extend_from_slice(&self.initialstacksize.to_le_bytes());
         objd_data.extend_from_slice(&self.base_draw_group_chunk_id.as_i16().to_le_bytes());
         objd_data.extend_from_slice(&self.draw_group_count.to_le_bytes());
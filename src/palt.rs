@@ -1,17 +1,13 @@
 use crate::error;
 use crate::iff;
 use crate::spr;
+use crate::sprite;
 
 use anyhow::Context;
 
 pub const PALT_COLOR_ENTRY_COUNT: u16 = 256;
 
-fn create_palt_chunk(palette_id: iff::IffChunkId, sprite_path: &std::path::Path) -> anyhow::Result<iff::IffChunk> {
-    const PALT_CHUNK_DATA_SIZE: usize = 784;
-    const PALT_VERSION: u32 = 1;
-
-    let palt_chunk_header = iff::IffChunkHeader::new(b"PALT", PALT_CHUNK_DATA_SIZE, palette_id, "")?;
-
+pub fn read_bmp_palette(sprite_path: &std::path::Path) -> anyhow::Result<Vec<[u8; 3]>> {
     let bmp_buffer = std::fs::File::open(sprite_path).with_context(|| error::file_read_error(sprite_path))?;
     let bmp_buffer = std::io::BufReader::new(&bmp_buffer);
     let sprite_bmp =
@@ -23,9 +19,20 @@ fn create_palt_chunk(palette_id: iff::IffChunkId, sprite_path: &std::path::Path)
         .to_vec();
     anyhow::ensure!(
         palette.len() == usize::from(PALT_COLOR_ENTRY_COUNT),
-        format!("{} does not have a 256 color palette", &sprite_path.display())
+        error::Error::NotA256ColorPalette {
+            path: sprite_path.to_owned()
+        }
     );
 
+    Ok(palette)
+}
+
+fn create_palt_chunk(palette_id: iff::IffChunkId, palette: &[[u8; 3]]) -> anyhow::Result<iff::IffChunk> {
+    const PALT_CHUNK_DATA_SIZE: usize = 784;
+    const PALT_VERSION: u32 = 1;
+
+    let palt_chunk_header = iff::IffChunkHeader::new(b"PALT", PALT_CHUNK_DATA_SIZE, palette_id, "")?;
+
     let palette: Vec<_> = palette.iter().flat_map(|entry| [entry[0], entry[1], entry[2]]).collect();
 
     let mut palt_data = std::vec::Vec::new();
@@ -42,9 +49,11 @@ fn create_palt_chunk(palette_id: iff::IffChunkId, sprite_path: &std::path::Path)
     })
 }
 
+/// Creates a PALT chunk for every palette used by the SPR2 sprites, from the palette of their first color channel.
 pub fn create_palt_chunks(
     source_directory: &std::path::Path,
     sprites: &[spr::Sprite],
+    split_sprites: &sprite::SplitSprites,
 ) -> anyhow::Result<Vec<iff::IffChunk>> {
     let mut palt_chunks = std::collections::HashMap::new();
 
@@ -62,10 +71,56 @@ pub fn create_palt_chunks(
                 let color_sprite_file_path = source_directory.join(
                     sprite_frame.sprite_channel_file_path_relative(spr::SpriteChannelType::Color, sprite.chunk_id)?,
                 );
-                entry.insert(create_palt_chunk(sprite.palette_chunk_id, &color_sprite_file_path)?);
+                let palette = match split_sprites.channel_images.get(&color_sprite_file_path) {
+                    Some(channel_image) => channel_image.palette.clone(),
+                    None => read_bmp_palette(&color_sprite_file_path)?,
+                };
+                entry.insert(create_palt_chunk(sprite.palette_chunk_id, &palette)?);
             }
         };
     }
 
     Ok(palt_chunks.into_values().collect())
 }
+
+pub fn read_palt_chunk(chunk: &iff::IffChunk) -> anyhow::Result<Vec<[u8; 3]>> {
+    let mut reader = iff::IffChunkReader::new(&chunk.data);
+
+    let _version = reader.read_u32()?;
+    let color_count = reader.read_u32()?;
+    anyhow::ensure!(
+        color_count <= u32::from(PALT_COLOR_ENTRY_COUNT),
+        "Palette {} has more than {} colors",
+        chunk.header.id().as_i16(),
+        PALT_COLOR_ENTRY_COUNT
+    );
+    let _reserved = reader.read_bytes(8)?;
+
+    let mut palette = Vec::with_capacity(usize::from(PALT_COLOR_ENTRY_COUNT));
+    for _ in 0..color_count {
+        let color = reader.read_bytes(3)?;
+        palette.push([color[0], color[1], color[2]]);
+    }
+    palette.resize(usize::from(PALT_COLOR_ENTRY_COUNT), [0, 0, 0]);
+
+    Ok(palette)
+}
+
+pub fn read_palt_chunks(iff: &iff::Iff) -> anyhow::Result<std::collections::HashMap<iff::IffChunkId, Vec<[u8; 3]>>> {
+    let mut palettes = std::collections::HashMap::new();
+    for chunk in iff.chunks.iter().filter(|x| x.header.chunk_type() == b"PALT") {
+        let palette = read_palt_chunk(chunk).with_context(|| {
+            format!(
+                "Failed to decode palette {} {}",
+                chunk.header.id().as_i16(),
+                chunk.header.label()
+            )
+        })?;
+        palettes.insert(chunk.header.id(), palette);
+    }
+    Ok(palettes)
+}
+
+pub fn grayscale_palette() -> Vec<[u8; 3]> {
+    (0..=255u8).map(|x| [x, x, x]).collect()
+}
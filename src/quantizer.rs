@@ -9,8 +9,10 @@ pub fn posterize_normalized(color: f32, bits: u8) -> f32 {
     posterize(color, bits) as f32 / 255.0
 }
 
+#[derive(Clone)]
 pub struct R5g6b5Image(image::RgbImage);
 
+/// Dithers a color sprite to the 16-bit colors that the game displays.
 pub fn dither_color_sprite_to_r5g6b5(image: image::RgbImage) -> R5g6b5Image {
     let mut image = image::DynamicImage::ImageRgb8(image).into_rgb32f();
     for y in 0..image.height() {
@@ -65,16 +67,28 @@ pub fn dither_color_sprite_to_r5g6b5(image: image::RgbImage) -> R5g6b5Image {
 const QUANTIZER_TRANSPARENT_COLOR: imagequant::RGBA = imagequant::RGBA::new(255, 255, 0, 1);
 const TRANSPARENT_COLOR_INDEX: u8 = 0;
 
+fn new_attributes() -> imagequant::Attributes {
+    let mut quantizer = imagequant::new();
+    quantizer.set_max_colors(u32::from(palt::PALT_COLOR_ENTRY_COUNT) - 1).unwrap();
+    quantizer
+}
+
+/// Collects the colors of all the sprites that share a palette.
 pub struct Histogram {
     quantizer: imagequant::Attributes,
     histogram: imagequant::Histogram,
     colors: std::collections::HashMap<imagequant::RGBA, u32>,
 }
 
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Histogram {
     pub fn new() -> Self {
-        let mut quantizer = imagequant::new();
-        quantizer.set_max_colors(u32::from(palt::PALT_COLOR_ENTRY_COUNT) - 1).unwrap();
+        let quantizer = new_attributes();
         let histogram = imagequant::Histogram::new(&quantizer);
         Histogram {
             quantizer,
@@ -98,6 +112,7 @@ impl Histogram {
         }
     }
 
+    /// Creates the palette and a quantizer for the collected colors.
     pub fn finalize(mut self) -> anyhow::Result<Quantizer> {
         anyhow::ensure!(!self.colors.is_empty(), "No colors added to histogram");
 
@@ -114,7 +129,8 @@ impl Histogram {
         // However it has very good color selection that gives a good palette to start with.
         // It also quantizes and dithers the final sprite well.
 
-        let histogram_colors: Vec<_> = self
+        // The colors are sorted so that the palette does not depend on the iteration order of the hash map.
+        let mut histogram_colors: Vec<_> = self
             .colors
             .iter()
             .map(|(color, count)| imagequant::HistogramEntry {
@@ -122,11 +138,12 @@ impl Histogram {
                 count: *count,
             })
             .collect();
+        histogram_colors.sort_by_key(|x| (x.color.r, x.color.g, x.color.b));
         self.histogram.add_colors(&histogram_colors, 0.0).unwrap();
         let mut quantization_result = self.histogram.quantize(&self.quantizer).unwrap();
 
         // Imagequants initial palette is converted to 16-bit R5G6B6 colors, ignoring duplicates.
-        let palette_set: std::collections::HashSet<[u8; 3]> = quantization_result
+        let palette_set: std::collections::BTreeSet<[u8; 3]> = quantization_result
             .palette()
             .iter()
             .map(|x| [posterize(x.r, 3), posterize(x.g, 2), posterize(x.b, 3)])
@@ -160,15 +177,34 @@ impl Histogram {
             palette
         };
 
+        Ok(Quantizer::from_palette(&palette))
+    }
+}
+
+/// Quantizes sprites to a palette created by [`Histogram::finalize`].
+pub struct Quantizer {
+    quantizer: imagequant::Attributes,
+    quantization_result: imagequant::QuantizationResult,
+    pub palette: Vec<[u8; 3]>,
+    pub transparent_color_index: u8,
+}
+
+impl Quantizer {
+    fn from_palette(palette: &[[u8; 3]]) -> Quantizer {
         // The final palette is created with the transparent color at the beginning.
         // All colors are added as fixed colors to an imagequant histogram, ready for it to quantize
         // the final sprites.
         // The transparent color has an alpha of 1 so that imagequant will not match it to any
         // pixels that are not transparent.
 
-        let mut histogram = imagequant::Histogram::new(&self.quantizer);
+        // With room for every fixed color imagequant takes them as the palette as they are. With one color less it
+        // runs median cut over the fixed colors, which all have a weight of 0 when there are no other colors and
+        // fail an assertion in debug builds, and the last color of the palette is never used.
+        let mut quantizer = imagequant::new();
+        quantizer.set_max_colors(u32::from(palt::PALT_COLOR_ENTRY_COUNT)).unwrap();
+        let mut histogram = imagequant::Histogram::new(&quantizer);
         histogram.add_fixed_color(QUANTIZER_TRANSPARENT_COLOR, 0.0).unwrap();
-        for color in &palette {
+        for color in palette {
             histogram
                 .add_fixed_color(
                     imagequant::RGBA {
@@ -181,9 +217,9 @@ impl Histogram {
                 )
                 .unwrap();
         }
-        let quantization_result = histogram.quantize(&self.quantizer).unwrap();
+        let quantization_result = histogram.quantize(&quantizer).unwrap();
         let mut final_palette = vec![[255, 255, 0]];
-        final_palette.extend(&palette);
+        final_palette.extend(palette);
 
         assert!(final_palette.len() == 256);
         for color in &final_palette {
@@ -192,23 +228,20 @@ impl Histogram {
             assert!(color[2] == posterize(color[2], 3));
         }
 
-        Ok(Quantizer {
-            quantizer: self.quantizer,
+        Quantizer {
+            quantizer,
             quantization_result,
             palette: final_palette,
             transparent_color_index: TRANSPARENT_COLOR_INDEX,
-        })
+        }
     }
-}
 
-pub struct Quantizer {
-    quantizer: imagequant::Attributes,
-    quantization_result: imagequant::QuantizationResult,
-    pub palette: Vec<[u8; 3]>,
-    pub transparent_color_index: u8,
-}
+    /// Creates another quantizer with the same palette, for quantizing on another thread.
+    pub fn duplicate(&self) -> Quantizer {
+        // the transparent color is always first in the final palette
+        Quantizer::from_palette(&self.palette[1..])
+    }
 
-impl Quantizer {
     pub fn quantize(&mut self, color: &R5g6b5Image, alpha: &image::Rgb32FImage) -> image::GrayImage {
         // For some reason imagequant produces noticeably bad results when quantizing the original
         // 24-bit image using the 256 R5G6B5 color palette.
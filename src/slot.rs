@@ -7,7 +7,7 @@ pub struct Slot {
     pub chunk_label: String,
     #[serde(rename = "@id")]
     pub chunk_id: iff::IffChunkId,
-    #[serde(rename = "slotdescriptor")]
+    #[serde(default, rename = "slotdescriptor")]
     pub slot_descriptors: Vec<SlotDescriptor>,
 }
 
@@ -64,6 +64,70 @@ pub struct SlotDescriptor {
 }
 
 impl Slot {
+    pub fn from_chunk(chunk: &iff::IffChunk) -> anyhow::Result<Slot> {
+        let mut reader = iff::IffChunkReader::new(&chunk.data);
+
+        let _ = reader.read_u32()?;
+        let version = reader.read_u32()?;
+        anyhow::ensure!(
+            reader.read_bytes(4)? == "TOLS".as_bytes(),
+            "Slot {} {} does not have a valid header",
+            chunk.header.id().as_i16(),
+            chunk.header.label()
+        );
+        let slot_descriptor_count = reader.read_u32()?;
+
+        // each version of the slot descriptor adds fields to the end of the previous version
+        let read_if = |reader: &mut iff::IffChunkReader, minimum_version: u32| -> anyhow::Result<Option<i32>> {
+            if version >= minimum_version {
+                Ok(Some(reader.read_i32()?))
+            } else {
+                Ok(None)
+            }
+        };
+
+        let mut slot_descriptors = Vec::new();
+        for _ in 0..slot_descriptor_count {
+            let slot_type = match reader.read_i16()? {
+                0 => SlotType::Zero,
+                1 => SlotType::One,
+                3 => SlotType::Three,
+                slot_type => anyhow::bail!(
+                    "Unknown slot type {} in slot {} {}",
+                    slot_type,
+                    chunk.header.id().as_i16(),
+                    chunk.header.label()
+                ),
+            };
+            slot_descriptors.push(SlotDescriptor {
+                slot_type,
+                xoffset: reader.read_f32()?,
+                yoffset: reader.read_f32()?,
+                altoffset: reader.read_f32()?,
+                standing: Some(reader.read_i32()?),
+                sitting: Some(reader.read_i32()?),
+                ground: Some(reader.read_i32()?),
+                rsflags: Some(reader.read_i32()?),
+                snaptargetslot: Some(reader.read_i32()?),
+                minproximity: read_if(&mut reader, 6)?,
+                maxproximity: read_if(&mut reader, 6)?,
+                optimalproximity: read_if(&mut reader, 6)?,
+                maxsize: read_if(&mut reader, 7)?,
+                flags: read_if(&mut reader, 8)?,
+                gradient: if version >= 8 { Some(reader.read_f32()?) } else { None },
+                height: read_if(&mut reader, 9)?,
+                facing: read_if(&mut reader, 10)?,
+                resolution: read_if(&mut reader, 10)?,
+            });
+        }
+
+        Ok(Slot {
+            chunk_label: chunk.header.label(),
+            chunk_id: chunk.header.id(),
+            slot_descriptors,
+        })
+    }
+
     pub fn to_chunk(&self) -> anyhow::Result<iff::IffChunk> {
         let mut slot_data = Vec::new();
 
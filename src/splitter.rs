@@ -1,5 +1,7 @@
 use crate::error;
 use crate::iff;
+use crate::message;
+use crate::palt;
 use crate::quantizer;
 use crate::sprite;
 
@@ -31,6 +33,12 @@ struct ObjectDescription {
     frames: Vec<FrameDescription>,
 }
 
+// the tiles of all sprites are split on several threads so they are counted together
+struct SplitProgress {
+    split_tile_count: std::sync::atomic::AtomicUsize,
+    tile_count: usize,
+}
+
 #[allow(clippy::too_many_arguments)]
 fn split_sprite(
     full_sprites_directory: &std::path::Path,
@@ -45,21 +53,22 @@ fn split_sprite(
     palette: &[[u8; 3]],
     palette_id: iff::IffChunkId,
     transparent_color_index: u8,
+    split_progress: &SplitProgress,
+    split_sprites: &mut sprite::SplitSprites,
 ) -> anyhow::Result<()> {
     let extra_tiles = (object_dimensions.x - 1) + (object_dimensions.y - 1);
 
-    let (tile_width, tile_height, split_sprite_width, split_sprite_height) = {
-        const SPRITE_WIDTH: i32 = 136;
-        const SPRITE_HEIGHT: i32 = 384;
+    let (tile_width, tile_height) = {
         const TILE_WIDTH: i32 = 128;
         const TILE_HEIGHT: i32 = 64;
 
         match zoom_level {
-            sprite::ZoomLevel::Zero => (TILE_WIDTH, TILE_HEIGHT, SPRITE_WIDTH, SPRITE_HEIGHT),
-            sprite::ZoomLevel::One => (TILE_WIDTH / 2, TILE_HEIGHT / 2, SPRITE_WIDTH / 2, SPRITE_HEIGHT / 2),
-            sprite::ZoomLevel::Two => (TILE_WIDTH / 4, TILE_HEIGHT / 4, SPRITE_WIDTH / 4, SPRITE_HEIGHT / 4),
+            sprite::ZoomLevel::Zero => (TILE_WIDTH, TILE_HEIGHT),
+            sprite::ZoomLevel::One => (TILE_WIDTH / 2, TILE_HEIGHT / 2),
+            sprite::ZoomLevel::Two => (TILE_WIDTH / 4, TILE_HEIGHT / 4),
         }
     };
+    let (split_sprite_width, split_sprite_height) = sprite::sprite_dimensions(zoom_level);
 
     let full_sprite_rotation_name = rotation.to_string();
     let full_sprite_z_file_name = zoom_level.to_string() + "_" + &full_sprite_rotation_name + "_depth.exr";
@@ -239,12 +248,6 @@ fn split_sprite(
 
             let split_sprite_frame_directory = split_sprites_directory.join(format!("{frame_name} {tile_x}_{tile_y}"));
 
-            if !split_sprite_frame_directory.is_dir() {
-                std::fs::create_dir_all(&split_sprite_frame_directory).with_context(|| {
-                    format!("Failed to create directory {}", split_sprite_frame_directory.display())
-                })?;
-            }
-
             let transmogrified_rotation = rotation.transmogrify();
 
             let split_sprite_p_file_path = sprite::sprite_channel_file_path(
@@ -266,43 +269,54 @@ fn split_sprite(
                 sprite::Channel::Alpha,
             );
 
-            {
-                let mut output_buffer = Vec::new();
-                let mut encoder = image::codecs::bmp::BmpEncoder::new(&mut output_buffer);
-                encoder
-                    .encode_with_palette(
-                        split_sprite_p.as_raw(),
-                        split_sprite_p.width(),
-                        split_sprite_p.height(),
-                        image::ExtendedColorType::L8,
-                        Some(palette),
-                    )
-                    .unwrap();
-
-                let mut file = std::fs::File::create(&split_sprite_p_file_path)
-                    .with_context(|| error::file_write_error(&split_sprite_p_file_path))?;
-                use std::io::Write;
-                file.write_all(&output_buffer).with_context(|| error::file_write_error(&split_sprite_p_file_path))?;
-            }
-            split_sprite_z
-                .save(&split_sprite_z_file_path)
-                .with_context(|| error::file_write_error(&split_sprite_z_file_path))?;
-            split_sprite_a
-                .save(&split_sprite_a_file_path)
-                .with_context(|| error::file_write_error(&split_sprite_a_file_path))?;
-
             let sprite_image_description = sprite::calculate_sprite_image_description(
                 &split_sprite_a,
                 zoom_level,
                 palette_id,
                 transparent_color_index,
             );
-            sprite::write_sprite_image_description_file(
-                &sprite_image_description,
-                &split_sprite_frame_directory,
-                zoom_level,
-                transmogrified_rotation,
-            )?;
+            split_sprites.image_descriptions.insert(
+                sprite::sprite_description_file_path(
+                    &split_sprite_frame_directory,
+                    zoom_level,
+                    transmogrified_rotation,
+                ),
+                sprite_image_description,
+            );
+
+            split_sprites.channel_images.insert(
+                split_sprite_p_file_path,
+                sprite::SpriteChannelImage {
+                    image: split_sprite_p,
+                    palette: palette.to_vec(),
+                },
+            );
+            split_sprites.channel_images.insert(
+                split_sprite_z_file_path,
+                sprite::SpriteChannelImage {
+                    image: split_sprite_z,
+                    palette: palt::grayscale_palette(),
+                },
+            );
+            split_sprites.channel_images.insert(
+                split_sprite_a_file_path,
+                sprite::SpriteChannelImage {
+                    image: split_sprite_a,
+                    palette: palt::grayscale_palette(),
+                },
+            );
+
+            let split_tile_count =
+                split_progress.split_tile_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+            message::progress(
+                message::Stage::Split,
+                split_tile_count,
+                split_progress.tile_count,
+                &message::ProgressLocation {
+                    tile: Some([tile_x, tile_y]),
+                    ..message::ProgressLocation::sprite(frame_name, rotation, zoom_level)
+                },
+            );
         }
     }
 
@@ -543,7 +557,87 @@ fn downsample_sprites(color: &image::RgbImage, alpha: &image::Rgb32FImage) -> (i
     downsample_horizontal(&color, &alpha)
 }
 
-pub fn split(source_directory: &std::path::Path, object_name: &str, variant: Option<&str>) -> anyhow::Result<()> {
+/// The directory that `split` writes the split sprites of an object to.
+pub fn split_sprites_directory(
+    source_directory: &std::path::Path,
+    object_name: &str,
+    variant: Option<&str>,
+) -> std::path::PathBuf {
+    let object_name = if let Some(variant) = variant {
+        format!("{} - {}", object_name, variant)
+    } else {
+        object_name.to_owned()
+    };
+    source_directory.join(format!("{} - sprites", object_name))
+}
+
+/// The number of threads to split with when no job count is given.
+pub fn default_job_count() -> usize {
+    std::thread::available_parallelism().map(|x| x.get()).unwrap_or(1)
+}
+
+/// Runs every job on up to `job_count` threads and returns the results in the order of the jobs. Each thread creates
+/// its own state with `init`.
+fn run_jobs<Job: Sync, State, Output: Send>(
+    jobs: &[Job],
+    job_count: usize,
+    init: impl Fn() -> State + Sync,
+    run: impl Fn(&mut State, &Job) -> Output + Sync,
+) -> Vec<Output> {
+    let next_job_index = std::sync::atomic::AtomicUsize::new(0);
+    let thread_count = job_count.clamp(1, jobs.len().max(1));
+
+    let mut outputs: Vec<_> = std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..thread_count)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut state = init();
+                    let mut outputs = Vec::new();
+                    loop {
+                        let job_index = next_job_index.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        let Some(job) = jobs.get(job_index) else {
+                            break;
+                        };
+                        outputs.push((job_index, run(&mut state, job)));
+                    }
+                    outputs
+                })
+            })
+            .collect();
+        threads
+            .into_iter()
+            .flat_map(|x| x.join().unwrap_or_else(|x| std::panic::resume_unwind(x)))
+            .collect()
+    });
+    outputs.sort_by_key(|(job_index, _)| *job_index);
+    outputs.into_iter().map(|(_, output)| output).collect()
+}
+
+/// Splits the rendered sprites of an object into their zoom levels and channels and quantizes them, using up to
+/// `job_count` threads. The output does not depend on the number of threads.
+pub fn split(
+    source_directory: &std::path::Path,
+    object_name: &str,
+    variant: Option<&str>,
+    job_count: usize,
+) -> anyhow::Result<()> {
+    let split_sprites = split_sprites(source_directory, object_name, variant, job_count)?;
+
+    let split_sprites_directory = split_sprites_directory(source_directory, object_name, variant);
+    if split_sprites_directory.is_dir() {
+        std::fs::remove_dir_all(&split_sprites_directory)
+            .with_context(|| format!("Failed to remove {}", split_sprites_directory.display()))?;
+    }
+    split_sprites.write()
+}
+
+/// Splits the rendered sprites of an object like [`split`] but keeps the split sprites in memory.
+pub fn split_sprites(
+    source_directory: &std::path::Path,
+    object_name: &str,
+    variant: Option<&str>,
+    job_count: usize,
+) -> anyhow::Result<sprite::SplitSprites> {
     let object_description = {
         let object_description_file_name = object_name.to_owned() + " - object description";
         let object_description_file_path = source_directory.join(object_description_file_name).with_extension("json");
@@ -608,6 +702,7 @@ pub fn split(source_directory: &std::path::Path, object_name: &str, variant: Opt
             .push(frame_description);
     }
 
+    let mut split_sprites = sprite::SplitSprites::default();
     for frame_descriptions in frame_palette_map.values() {
         split_palette(
             source_directory,
@@ -617,12 +712,54 @@ pub fn split(source_directory: &std::path::Path, object_name: &str, variant: Opt
             frame_descriptions,
             object_description.frames[0].palette_id,
             &depth_planes,
+            job_count,
+            &mut split_sprites,
         )?;
     }
 
-    Ok(())
+    Ok(split_sprites)
 }
 
+struct FullSprite {
+    color_sprite: image::RgbImage,
+    alpha_sprite: image::Rgb32FImage,
+    dithered_color_sprite: quantizer::R5g6b5Image,
+}
+
+fn load_full_sprite(
+    full_sprites_directory: &std::path::Path,
+    frame_name: &str,
+    rotation: sprite::Rotation,
+) -> anyhow::Result<Option<FullSprite>> {
+    let full_sprite_frame_directory = full_sprites_directory.join(frame_name);
+
+    let color_sprite_file_name = rotation.to_string() + "_color.png";
+    let color_sprite_file_path = full_sprite_frame_directory.join(color_sprite_file_name);
+    if !color_sprite_file_path.is_file() {
+        return Ok(None);
+    }
+    let color_sprite = image::open(&color_sprite_file_path)
+        .with_context(|| error::file_read_error(&color_sprite_file_path))?
+        .to_rgb8();
+
+    let alpha_sprite_file_name = rotation.to_string() + "_alpha.exr";
+    let alpha_sprite_file_path = full_sprite_frame_directory.join(alpha_sprite_file_name);
+    let alpha_sprite = image::open(&alpha_sprite_file_path)
+        .with_context(|| error::file_read_error(&alpha_sprite_file_path))?
+        .to_rgb32f();
+
+    let (color_sprite, alpha_sprite) = downsample_sprites(&color_sprite, &alpha_sprite);
+
+    let dithered_color_sprite = quantizer::dither_color_sprite_to_r5g6b5(color_sprite.clone());
+
+    Ok(Some(FullSprite {
+        color_sprite,
+        alpha_sprite,
+        dithered_color_sprite,
+    }))
+}
+
+#[allow(clippy::too_many_arguments)]
 fn split_palette(
     source_directory: &std::path::Path,
     object_name: &str,
@@ -631,62 +768,42 @@ fn split_palette(
     frame_descriptions: &[&FrameDescription],
     palette_id: iff::IffChunkId,
     depth_planes: &DepthPlanes,
+    job_count: usize,
+    split_sprites: &mut sprite::SplitSprites,
 ) -> anyhow::Result<()> {
+    let split_sprites_directory = split_sprites_directory(source_directory, object_name, variant);
     let object_name = if let Some(variant) = variant {
         format!("{} - {}", object_name, variant)
     } else {
         object_name.to_owned()
     };
     let full_sprites_directory = source_directory.join(format!("{} - full sprites", object_name));
-    let split_sprites_directory = source_directory.join(format!("{} - sprites", object_name));
-    if split_sprites_directory.is_dir() {
-        std::fs::remove_dir_all(&split_sprites_directory)
-            .with_context(|| format!("Failed to remove {}", split_sprites_directory.display()))?;
-    }
 
-    let mut sprites = Vec::new();
+    // the full sprites are loaded and dithered in parallel, but added to the histogram in order
+    let full_sprite_jobs: Vec<_> = frame_descriptions
+        .iter()
+        .flat_map(|frame_description| ROTATIONS.map(|rotation| (*frame_description, rotation)))
+        .collect();
+    let full_sprites = run_jobs(
+        &full_sprite_jobs,
+        job_count,
+        || (),
+        |_, (frame_description, rotation)| {
+            load_full_sprite(&full_sprites_directory, &frame_description.name, *rotation)
+        },
+    );
 
+    let mut full_sprites = full_sprites.into_iter();
+    let mut sprites = Vec::new();
     let mut histogram = quantizer::Histogram::new();
-
     for frame_description in frame_descriptions {
         let sprite_count = sprites.len();
-        let rotations = [
-            sprite::Rotation::NorthWest,
-            sprite::Rotation::NorthEast,
-            sprite::Rotation::SouthEast,
-            sprite::Rotation::SouthWest,
-        ];
-        for rotation in rotations {
-            let full_sprite_frame_directory = full_sprites_directory.join(&frame_description.name);
-
-            let color_sprite_file_name = rotation.to_string() + "_color.png";
-            let color_sprite_file_path = full_sprite_frame_directory.join(color_sprite_file_name);
-            if !color_sprite_file_path.is_file() {
+        for rotation in ROTATIONS {
+            let Some(full_sprite) = full_sprites.next().unwrap()? else {
                 continue;
-            }
-            let color_sprite = image::open(&color_sprite_file_path)
-                .with_context(|| error::file_read_error(&color_sprite_file_path))?
-                .to_rgb8();
-
-            let alpha_sprite_file_name = rotation.to_string() + "_alpha.exr";
-            let alpha_sprite_file_path = full_sprite_frame_directory.join(alpha_sprite_file_name);
-            let alpha_sprite = image::open(&alpha_sprite_file_path)
-                .with_context(|| error::file_read_error(&alpha_sprite_file_path))?
-                .to_rgb32f();
-
-            let (color_sprite, alpha_sprite) = downsample_sprites(&color_sprite, &alpha_sprite);
-
-            let dithered_color_sprite = quantizer::dither_color_sprite_to_r5g6b5(color_sprite.clone());
-
-            histogram.add_colors(&dithered_color_sprite, &alpha_sprite);
-
-            sprites.push((
-                &frame_description.name,
-                rotation,
-                color_sprite,
-                alpha_sprite,
-                dithered_color_sprite,
-            ));
+            };
+            histogram.add_colors(&full_sprite.dithered_color_sprite, &full_sprite.alpha_sprite);
+            sprites.push((&frame_description.name, rotation, full_sprite));
         }
 
         anyhow::ensure!(
@@ -696,61 +813,65 @@ fn split_palette(
         );
     }
 
-    let mut quantizer = histogram
+    let quantizer = histogram
         .finalize()
         .with_context(|| format!("No sprites found in {}", full_sprites_directory.display()))?;
 
-    for (frame_name, rotation, color_sprite, alpha_sprite, dithered_color_sprite) in sprites {
-        split_sprite(
-            &full_sprites_directory,
-            &split_sprites_directory,
-            object_dimensions,
-            frame_name,
-            rotation,
-            sprite::ZoomLevel::Zero,
-            &quantizer.quantize(&dithered_color_sprite, &alpha_sprite),
-            &alpha_sprite,
-            &depth_planes.large(),
-            &quantizer.palette,
-            palette_id,
-            quantizer.transparent_color_index,
-        )?;
-
-        let (color_sprite, alpha_sprite) = downsample_sprites(&color_sprite, &alpha_sprite);
-        let dithered_color_sprite = quantizer::dither_color_sprite_to_r5g6b5(color_sprite.clone());
-
-        split_sprite(
-            &full_sprites_directory,
-            &split_sprites_directory,
-            object_dimensions,
-            frame_name,
-            rotation,
-            sprite::ZoomLevel::One,
-            &quantizer.quantize(&dithered_color_sprite, &alpha_sprite),
-            &alpha_sprite,
-            &depth_planes.medium(),
-            &quantizer.palette,
-            palette_id,
-            quantizer.transparent_color_index,
-        )?;
+    // every zoom level of every sprite is quantized and split on its own, the tiles of a sprite are split in order
+    // because each tile takes its pixels away from the tiles after it
+    let split_jobs: Vec<_> =
+        sprites.iter().flat_map(|sprite| ZOOM_LEVELS.map(|zoom_level| (sprite, zoom_level))).collect();
+    let split_progress = SplitProgress {
+        split_tile_count: std::sync::atomic::AtomicUsize::new(0),
+        tile_count: split_jobs.len() * usize::try_from(object_dimensions.x * object_dimensions.y).unwrap(),
+    };
+    let split_results: Vec<anyhow::Result<_>> = run_jobs(
+        &split_jobs,
+        job_count,
+        || quantizer.duplicate(),
+        |quantizer, ((frame_name, rotation, full_sprite), zoom_level)| {
+            let (alpha_sprite, dithered_color_sprite) = match zoom_level {
+                sprite::ZoomLevel::Zero => (
+                    full_sprite.alpha_sprite.clone(),
+                    full_sprite.dithered_color_sprite.clone(),
+                ),
+                sprite::ZoomLevel::One | sprite::ZoomLevel::Two => {
+                    let (mut color_sprite, mut alpha_sprite) =
+                        downsample_sprites(&full_sprite.color_sprite, &full_sprite.alpha_sprite);
+                    if *zoom_level == sprite::ZoomLevel::Two {
+                        (color_sprite, alpha_sprite) = downsample_sprites(&color_sprite, &alpha_sprite);
+                    }
+                    (alpha_sprite, quantizer::dither_color_sprite_to_r5g6b5(color_sprite))
+                }
+            };
+            let depth_planes = match zoom_level {
+                sprite::ZoomLevel::Zero => depth_planes.large(),
+                sprite::ZoomLevel::One => depth_planes.medium(),
+                sprite::ZoomLevel::Two => depth_planes.small(),
+            };
 
-        let (color_sprite, alpha_sprite) = downsample_sprites(&color_sprite, &alpha_sprite);
-        let dithered_color_sprite = quantizer::dither_color_sprite_to_r5g6b5(color_sprite.clone());
-
-        split_sprite(
-            &full_sprites_directory,
-            &split_sprites_directory,
-            object_dimensions,
-            frame_name,
-            rotation,
-            sprite::ZoomLevel::Two,
-            &quantizer.quantize(&dithered_color_sprite, &alpha_sprite),
-            &alpha_sprite,
-            &depth_planes.small(),
-            &quantizer.palette,
-            palette_id,
-            quantizer.transparent_color_index,
-        )?;
+            let mut job_split_sprites = sprite::SplitSprites::default();
+            split_sprite(
+                &full_sprites_directory,
+                &split_sprites_directory,
+                object_dimensions,
+                frame_name,
+                *rotation,
+                *zoom_level,
+                &quantizer.quantize(&dithered_color_sprite, &alpha_sprite),
+                &alpha_sprite,
+                &depth_planes,
+                &quantizer.palette,
+                palette_id,
+                quantizer.transparent_color_index,
+                &split_progress,
+                &mut job_split_sprites,
+            )?;
+            Ok(job_split_sprites)
+        },
+    );
+    for split_result in split_results {
+        split_sprites.extend(split_result?);
     }
 
     for frame_description in frame_descriptions {
@@ -758,12 +879,8 @@ fn split_palette(
             for x in 0..object_dimensions.x {
                 let split_sprite_frame_directory =
                     split_sprites_directory.join(format!("{} {x}_{y}", frame_description.name));
-                if !split_sprite_frame_directory.is_dir() {
-                    continue;
-                }
-                if is_tile_empty(&split_sprite_frame_directory)? {
-                    std::fs::remove_dir_all(&split_sprite_frame_directory)
-                        .with_context(|| format!("Failed to remove {}", split_sprite_frame_directory.display()))?;
+                if is_tile_empty(split_sprites, &split_sprite_frame_directory) {
+                    remove_tile(split_sprites, &split_sprite_frame_directory);
                 } else {
                     let sprite_id = {
                         let x = if frame_description.sprite_id_reverse_x {
@@ -778,14 +895,7 @@ fn split_palette(
                         };
                         frame_description.sprite_id + i16::try_from(x + (y * object_dimensions.x)).unwrap()
                     };
-
-                    let tile_sprite_id_file_path =
-                        split_sprite_frame_directory.join("sprite id").with_extension("json");
-                    let json_string = serde_json::to_string_pretty(&sprite_id).with_context(|| {
-                        format!("Failed to serialize json file {}", tile_sprite_id_file_path.display())
-                    })?;
-                    std::fs::write(&tile_sprite_id_file_path, json_string)
-                        .with_context(|| error::file_write_error(&tile_sprite_id_file_path))?;
+                    split_sprites.sprite_ids.insert(split_sprite_frame_directory, sprite_id);
                 }
             }
         }
@@ -794,33 +904,128 @@ fn split_palette(
     Ok(())
 }
 
-fn is_tile_empty(split_sprite_frame_tile_directory: &std::path::Path) -> anyhow::Result<bool> {
-    let rotations = [
-        sprite::Rotation::NorthWest,
-        sprite::Rotation::NorthEast,
-        sprite::Rotation::SouthEast,
-        sprite::Rotation::SouthWest,
-    ];
-    let zoom_levels = [sprite::ZoomLevel::Zero, sprite::ZoomLevel::One, sprite::ZoomLevel::Two];
-    for rotation in rotations {
-        for zoom_level in zoom_levels {
+const ROTATIONS: [sprite::Rotation; 4] = [
+    sprite::Rotation::NorthWest,
+    sprite::Rotation::NorthEast,
+    sprite::Rotation::SouthEast,
+    sprite::Rotation::SouthWest,
+];
+const ZOOM_LEVELS: [sprite::ZoomLevel; 3] = [sprite::ZoomLevel::Zero, sprite::ZoomLevel::One, sprite::ZoomLevel::Two];
+
+fn is_tile_empty(split_sprites: &sprite::SplitSprites, split_sprite_frame_tile_directory: &std::path::Path) -> bool {
+    for rotation in ROTATIONS {
+        for zoom_level in ZOOM_LEVELS {
             let split_sprite_a_file_path = sprite::sprite_channel_file_path(
                 split_sprite_frame_tile_directory,
                 zoom_level,
                 rotation,
                 sprite::Channel::Alpha,
             );
-            if !split_sprite_a_file_path.is_file() {
+            let Some(split_sprite_a) = split_sprites.channel_images.get(&split_sprite_a_file_path) else {
                 continue;
+            };
+            if split_sprite_a.image.pixels().any(|x| x[0] != 0) {
+                return false;
             }
-            let split_sprite_a = image::open(&split_sprite_a_file_path)
-                .with_context(|| error::file_read_error(&split_sprite_a_file_path))?
-                .to_luma8();
-            if split_sprite_a.pixels().any(|x| x[0] != 0) {
-                return Ok(false);
+        }
+    }
+
+    true
+}
+
+fn remove_tile(split_sprites: &mut sprite::SplitSprites, split_sprite_frame_tile_directory: &std::path::Path) {
+    for rotation in ROTATIONS {
+        for zoom_level in ZOOM_LEVELS {
+            for channel in [sprite::Channel::Color, sprite::Channel::Depth, sprite::Channel::Alpha] {
+                split_sprites.channel_images.remove(&sprite::sprite_channel_file_path(
+                    split_sprite_frame_tile_directory,
+                    zoom_level,
+                    rotation,
+                    channel,
+                ));
+            }
+            split_sprites.image_descriptions.remove(&sprite::sprite_description_file_path(
+                split_sprite_frame_tile_directory,
+                zoom_level,
+                rotation,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the split sprites with their images and descriptions in a form that can be compared
+    #[derive(PartialEq)]
+    struct ComparableSplitSprites {
+        sprite_ids: std::collections::BTreeMap<std::path::PathBuf, iff::IffChunkId>,
+        channel_images: std::collections::BTreeMap<std::path::PathBuf, (Vec<u8>, Vec<[u8; 3]>)>,
+        image_descriptions: std::collections::BTreeMap<std::path::PathBuf, String>,
+    }
+
+    impl ComparableSplitSprites {
+        fn new(split_sprites: &sprite::SplitSprites) -> ComparableSplitSprites {
+            ComparableSplitSprites {
+                sprite_ids: split_sprites.sprite_ids.clone(),
+                channel_images: split_sprites
+                    .channel_images
+                    .iter()
+                    .map(|(path, x)| (path.clone(), (x.image.as_raw().clone(), x.palette.clone())))
+                    .collect(),
+                image_descriptions: split_sprites
+                    .image_descriptions
+                    .iter()
+                    .map(|(path, x)| (path.clone(), serde_json::to_string(x).unwrap()))
+                    .collect(),
             }
         }
     }
 
-    Ok(true)
+    #[test]
+    fn split_sprites_do_not_depend_on_the_job_count() {
+        let source_directory = std::env::temp_dir().join(format!("ts1-compiler-split-{}", std::process::id()));
+        let full_sprite_frame_directory = source_directory.join("test - full sprites").join("base");
+        std::fs::create_dir_all(&full_sprite_frame_directory).unwrap();
+        std::fs::write(
+            source_directory.join("test - object description.json"),
+            r#"{"dimensions":{"x":1,"y":1},"frames":[{"name":"base","sprite_id":100,"palette_id":5}]}"#,
+        )
+        .unwrap();
+
+        // two rotations of a 1x1 object, a shaded block with a soft edge in the middle of the sprite
+        for rotation in [sprite::Rotation::NorthWest, sprite::Rotation::SouthEast] {
+            let color_sprite = image::RgbImage::from_fn(272, 768, |x, y| image::Rgb([x as u8, y as u8, (x ^ y) as u8]));
+            let alpha_sprite = image::Rgb32FImage::from_fn(272, 768, |x, y| {
+                let alpha = match (x, y) {
+                    (96..176, 448..640) => 1.0,
+                    (88..184, 440..648) => 0.5,
+                    _ => 0.0,
+                };
+                image::Rgb([alpha; 3])
+            });
+            color_sprite.save(full_sprite_frame_directory.join(format!("{rotation}_color.png"))).unwrap();
+            image::DynamicImage::ImageRgb32F(alpha_sprite)
+                .save(full_sprite_frame_directory.join(format!("{rotation}_alpha.exr")))
+                .unwrap();
+            for zoom_level in ZOOM_LEVELS {
+                let (width, height) = sprite::sprite_dimensions(zoom_level);
+                let depth_sprite =
+                    image::Rgb32FImage::from_pixel(width as u32, height as u32, image::Rgb([20.0, 20.0, 20.0]));
+                image::DynamicImage::ImageRgb32F(depth_sprite)
+                    .save(full_sprite_frame_directory.join(format!("{zoom_level}_{rotation}_depth.exr")))
+                    .unwrap();
+            }
+        }
+
+        let serial_split_sprites = split_sprites(&source_directory, "test", None, 1);
+        let parallel_split_sprites = split_sprites(&source_directory, "test", None, 4);
+        std::fs::remove_dir_all(&source_directory).unwrap();
+
+        let serial_split_sprites = ComparableSplitSprites::new(&serial_split_sprites.unwrap());
+        let parallel_split_sprites = ComparableSplitSprites::new(&parallel_split_sprites.unwrap());
+        assert!(!serial_split_sprites.channel_images.is_empty());
+        assert!(serial_split_sprites == parallel_split_sprites);
+    }
 }
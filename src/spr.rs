@@ -70,6 +70,25 @@ impl Sprite {
             sprite_frames,
         }
     }
+
+    pub fn new_spr1(
+        chunk_label: &str,
+        chunk_id: iff::IffChunkId,
+        palette_chunk_id: iff::IffChunkId,
+        is_custom_wall_style: bool,
+        sprite_frames: Vec<SpriteFrame>,
+    ) -> Sprite {
+        Sprite {
+            chunk_label: chunk_label.to_owned(),
+            chunk_id,
+            sprite_type: SpriteType::Spr1,
+            multi_tile: 0,
+            palette_chunk_id,
+            sprite_frame_count: sprite_frames.len().try_into().unwrap(),
+            is_custom_wall_style,
+            sprite_frames,
+        }
+    }
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -141,6 +160,37 @@ impl SpriteFrame {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_spr1(
+        index: i32,
+        zoom_level: sprite::ZoomLevel,
+        rotation: sprite::Rotation,
+        width: i16,
+        height: i16,
+        palette_chunk_id: iff::IffChunkId,
+        transparent_color_index: u8,
+        sprite_channel_z_relative_path: &std::path::Path,
+    ) -> SpriteFrame {
+        let sprite_channels = vec![SpriteChannel {
+            channel_type: SpriteChannelType::Depth,
+            file_path_relative: sprite_channel_z_relative_path.to_str().unwrap().to_owned(),
+        }];
+        SpriteFrame {
+            index: SpriteIndex(index),
+            zoom_level,
+            rotation,
+            bounds_left: 0,
+            bounds_top: 0,
+            cropped_width: width,
+            cropped_height: height,
+            width,
+            height,
+            palette_chunk_id,
+            transparent_color_index,
+            sprite_channels,
+        }
+    }
+
     pub fn sprite_channel_file_path_relative(
         &self,
         channel_type: SpriteChannelType,
@@ -172,16 +222,15 @@ fn sprite_channel_error(
     channel_type: SpriteChannelType,
     sprite_id: iff::IffChunkId,
     frame_index: SpriteIndex,
-) -> String {
-    format!(
-        "Failed to find {} channel in sprite id: {} frame: {}",
-        channel_type,
-        sprite_id.as_i16(),
-        frame_index.0
-    )
+) -> error::Error {
+    error::Error::MissingSpriteChannel {
+        chunk_id: sprite_id,
+        frame_index: frame_index.0,
+        channel: channel_type,
+    }
 }
 
-#[derive(Copy, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Copy, Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 #[serde(deny_unknown_fields)]
 pub enum SpriteChannelType {
     #[serde(rename = "p")]
@@ -213,10 +262,16 @@ struct SpriteChannel {
 }
 
 impl Sprite {
-    pub fn to_chunk(&self, source_directory: &std::path::Path) -> anyhow::Result<iff::IffChunk> {
+    /// Builds the SPR# or SPR2 chunk from the sprite channel images in the source directory, or from the split sprites
+    /// held in memory.
+    pub fn to_chunk(
+        &self,
+        source_directory: &std::path::Path,
+        split_sprites: &sprite::SplitSprites,
+    ) -> anyhow::Result<iff::IffChunk> {
         match self.sprite_type {
             SpriteType::Spr1 => self.to_spr1_chunk(source_directory),
-            SpriteType::Spr2 => self.to_spr2_chunk(source_directory),
+            SpriteType::Spr2 => self.to_spr2_chunk(source_directory, split_sprites),
         }
     }
 
@@ -226,13 +281,8 @@ impl Sprite {
         let mut frame_datas = std::vec::Vec::new();
         for frame in &self.sprite_frames {
             let (width, height, pixels) = {
-                let channel_type = if self.is_custom_wall_style {
-                    SpriteChannelType::Depth
-                } else {
-                    SpriteChannelType::Color
-                };
-                let file_path =
-                    source_directory.join(frame.sprite_channel_file_path_relative(channel_type, self.chunk_id)?);
+                let file_path = source_directory
+                    .join(frame.sprite_channel_file_path_relative(SpriteChannelType::Depth, self.chunk_id)?);
                 let mut bmp = read_bmp(&file_path)?;
                 bmp.set_indexed_color(true);
                 let (width, height) = bmp.dimensions();
@@ -349,7 +399,7 @@ impl Sprite {
                             }
                             if range_x + 1 == width {
                                 let mut unique_range = ongoing_unique_range.unwrap_or_default();
-                                unique_range.push(pixels[row_index + x]);
+                                unique_range.push(pixels[row_index + range_x]);
                                 ongoing_unique_range = Some(unique_range);
 
                                 range_x += 1;
@@ -359,7 +409,7 @@ impl Sprite {
 
                             if next_pixel == transparent_color_index {
                                 let mut unique_range = ongoing_unique_range.unwrap_or_default();
-                                unique_range.push(pixels[row_index + x]);
+                                unique_range.push(pixels[row_index + range_x]);
                                 ongoing_unique_range = Some(unique_range);
 
                                 range_x += 1;
@@ -386,7 +436,7 @@ impl Sprite {
                                     row_commands.extend_from_slice(&row_command_length.to_le_bytes());
 
                                     if self.palette_chunk_id.as_i16().is_positive() {
-                                        row_commands.push(pixels[row_index + range_x + x]);
+                                        row_commands.push(pixels[row_index + range_x]);
                                     } else {
                                         row_commands.push(0);
                                     }
@@ -418,7 +468,9 @@ impl Sprite {
                                 }
 
                                 let mut unique_range = ongoing_unique_range.unwrap_or_default();
-                                unique_range.extend_from_slice(&pixels[row_index + x..row_index + x + unique_width]);
+                                unique_range.extend_from_slice(
+                                    &pixels[row_index + range_x..row_index + range_x + unique_width],
+                                );
                                 ongoing_unique_range = Some(unique_range);
 
                                 range_x += unique_width;
@@ -487,7 +539,11 @@ impl Sprite {
         })
     }
 
-    fn to_spr2_chunk(&self, source_directory: &std::path::Path) -> anyhow::Result<iff::IffChunk> {
+    fn to_spr2_chunk(
+        &self,
+        source_directory: &std::path::Path,
+        split_sprites: &sprite::SplitSprites,
+    ) -> anyhow::Result<iff::IffChunk> {
         assert!(self.sprite_type == SpriteType::Spr2);
 
         let mut frame_datas = std::vec::Vec::new();
@@ -505,16 +561,10 @@ impl Sprite {
                 let x = u32::try_from(frame.bounds_left).unwrap();
                 let y = u32::try_from(frame.bounds_top).unwrap();
 
-                let mut bmp_p = read_bmp(&file_path_p)?;
-                let mut bmp_z = read_bmp(&file_path_z)?;
-                let mut bmp_a = read_bmp(&file_path_a)?;
-
-                let pixels_p = read_bmp_rect(&mut bmp_p, x, y, std::cmp::max(width, 1), std::cmp::max(height, 1))
-                    .with_context(|| error::file_read_error(&file_path_p))?;
-                let pixels_z = read_bmp_rect(&mut bmp_z, x, y, std::cmp::max(width, 1), std::cmp::max(height, 1))
-                    .with_context(|| error::file_read_error(&file_path_z))?;
-                let pixels_a = read_bmp_rect(&mut bmp_a, x, y, std::cmp::max(width, 1), std::cmp::max(height, 1))
-                    .with_context(|| error::file_read_error(&file_path_a))?;
+                let (rect_width, rect_height) = (std::cmp::max(width, 1), std::cmp::max(height, 1));
+                let pixels_p = read_channel_rect(split_sprites, &file_path_p, x, y, rect_width, rect_height)?;
+                let pixels_z = read_channel_rect(split_sprites, &file_path_z, x, y, rect_width, rect_height)?;
+                let pixels_a = read_channel_rect(split_sprites, &file_path_a, x, y, rect_width, rect_height)?;
 
                 (pixels_p, pixels_z, pixels_a)
             };
@@ -541,8 +591,9 @@ impl Sprite {
                 End,
             }
 
-            fn row_command(command: RowCommand, size_or_length: u16) -> u16 {
-                assert!(size_or_length <= 0b0001111111111111);
+            // the count or size has to fit into the 13 bits below the command
+            fn row_command(command: RowCommand, size_or_length: usize) -> Option<u16> {
+                let size_or_length = u16::try_from(size_or_length).ok().filter(|x| *x <= 0b0001111111111111)?;
                 let row_command_bits = match command {
                     RowCommand::Start => 0b0000000000000000,
                     RowCommand::Opaque => 0b0010000000000000,
@@ -551,9 +602,15 @@ impl Sprite {
                     RowCommand::TransparentRows => 0b1000000000000000,
                     RowCommand::End => 0b1010000000000000,
                 };
-                row_command_bits | size_or_length
+                Some(row_command_bits | size_or_length)
             }
 
+            let row_command_overflow = |y| error::Error::RowCommandOverflow {
+                chunk_id: self.chunk_id,
+                frame_index: frame.index.0,
+                row: y,
+            };
+
             let mut y = 0;
             while y < height {
                 let mut row_commands = std::vec::Vec::new();
@@ -563,8 +620,8 @@ impl Sprite {
                 if let Some(i) = pixels_a[row_index..].iter().position(|x| *x != 0) {
                     let transparent_row_count = i / width;
                     if transparent_row_count >= 1 {
-                        let row_command_length = u16::try_from(transparent_row_count).unwrap();
-                        let row_command = row_command(RowCommand::TransparentRows, row_command_length);
+                        let row_command = row_command(RowCommand::TransparentRows, transparent_row_count)
+                            .with_context(|| row_command_overflow(y))?;
                         frame_data.extend_from_slice(&row_command.to_le_bytes());
 
                         y += transparent_row_count;
@@ -590,8 +647,8 @@ impl Sprite {
                             break;
                         }
 
-                        let row_command_length = u16::try_from(transparent_width).unwrap();
-                        let row_command = row_command(RowCommand::Transparent, row_command_length);
+                        let row_command = row_command(RowCommand::Transparent, transparent_width)
+                            .with_context(|| row_command_overflow(y))?;
                         row_commands.extend_from_slice(&row_command.to_le_bytes());
 
                         x += transparent_width;
@@ -607,8 +664,8 @@ impl Sprite {
                             }
                         }
 
-                        let row_command_length = u16::try_from(translucent_color_width).unwrap();
-                        let row_command = row_command(RowCommand::Translucent, row_command_length);
+                        let row_command = row_command(RowCommand::Translucent, translucent_color_width)
+                            .with_context(|| row_command_overflow(y))?;
                         row_commands.extend_from_slice(&row_command.to_le_bytes());
 
                         for x in x..x + translucent_color_width {
@@ -634,8 +691,8 @@ impl Sprite {
                             }
                         }
 
-                        let row_command_length = u16::try_from(color_width).unwrap();
-                        let row_command = row_command(RowCommand::Opaque, row_command_length);
+                        let row_command =
+                            row_command(RowCommand::Opaque, color_width).with_context(|| row_command_overflow(y))?;
                         row_commands.extend_from_slice(&row_command.to_le_bytes());
 
                         for x in x..x + color_width {
@@ -647,8 +704,8 @@ impl Sprite {
                     }
                 }
 
-                let row_command_length = 2 + u16::try_from(row_commands.len()).unwrap();
-                let row_command = row_command(RowCommand::Start, row_command_length);
+                let row_command =
+                    row_command(RowCommand::Start, 2 + row_commands.len()).with_context(|| row_command_overflow(y))?;
                 frame_data.extend_from_slice(&row_command.to_le_bytes());
 
                 frame_data.extend_from_slice(row_commands.as_slice());
@@ -656,7 +713,7 @@ impl Sprite {
                 y += 1;
             }
 
-            let row_command = row_command(RowCommand::End, 0);
+            let row_command = row_command(RowCommand::End, 0).unwrap();
             frame_data.extend_from_slice(&row_command.to_le_bytes());
 
             frame_datas.push(frame_data);
@@ -689,7 +746,304 @@ impl Sprite {
     }
 }
 
-fn read_bmp(
+pub struct Spr1 {
+    pub palette_chunk_id: iff::IffChunkId,
+    pub frames: Vec<Spr1Frame>,
+}
+
+pub struct Spr1Frame {
+    pub width: u16,
+    pub height: u16,
+    pub transparent_color_index: u8,
+    pub pixels: Vec<u8>,
+}
+
+impl Spr1Frame {
+    pub fn image(&self) -> image::GrayImage {
+        image::GrayImage::from_raw(u32::from(self.width), u32::from(self.height), self.pixels.clone()).unwrap()
+    }
+}
+
+pub fn decode_spr1_chunk(chunk: &iff::IffChunk) -> anyhow::Result<Spr1> {
+    let mut reader = iff::IffChunkReader::new(&chunk.data);
+
+    let version = reader.read_u32()?;
+    anyhow::ensure!(
+        version == 504 || version == 505,
+        error::Error::UnsupportedVersion {
+            chunk_type: *chunk.header.chunk_type(),
+            chunk_id: chunk.header.id(),
+            chunk_label: chunk.header.label(),
+            version,
+        }
+    );
+    let frame_count = reader.read_u32()?;
+    let palette_chunk_id = iff::IffChunkId::new(i16::try_from(reader.read_i32()?)?);
+    let frame_addresses = (0..frame_count).map(|_| reader.read_u32()).collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut frames = Vec::new();
+    for (frame_index, frame_address) in frame_addresses.into_iter().enumerate() {
+        reader.seek(usize::try_from(frame_address).unwrap())?;
+        let frame = decode_spr1_frame(&mut reader).with_context(|| {
+            format!(
+                "Failed to decode frame {} of sprite {} {}",
+                frame_index,
+                chunk.header.id().as_i16(),
+                chunk.header.label()
+            )
+        })?;
+        frames.push(frame);
+    }
+
+    Ok(Spr1 {
+        palette_chunk_id,
+        frames,
+    })
+}
+
+fn decode_spr1_frame(reader: &mut iff::IffChunkReader) -> anyhow::Result<Spr1Frame> {
+    let _ = reader.read_u32()?;
+    let height = reader.read_u16()?;
+    let width = reader.read_u16()?;
+
+    let width_usize = usize::from(width);
+    let height_usize = usize::from(height);
+
+    let mut pixels = vec![0u8; width_usize * height_usize];
+    let mut opaque = vec![false; width_usize * height_usize];
+
+    let mut y = 0;
+    loop {
+        let command = reader.read_u8()?;
+        let count = usize::from(reader.read_u8()?);
+        match command {
+            // start sprite
+            0 => (),
+            // start row
+            4 => {
+                anyhow::ensure!(y < height_usize, "Row {} is outside of the frame", y);
+                let row_end = reader.position() - 2 + count;
+                let mut x = 0;
+                while reader.position() < row_end {
+                    let command = reader.read_u8()?;
+                    let count = usize::from(reader.read_u8()?);
+                    anyhow::ensure!(x + count <= width_usize, "Row {} is wider than the frame", y);
+                    let row_index = y * width_usize;
+                    match command {
+                        // transparent
+                        1 => (),
+                        // opaque repeat
+                        2 => {
+                            let color = reader.read_u8()?;
+                            let _ = reader.read_u8()?;
+                            pixels[row_index + x..row_index + x + count].fill(color);
+                            opaque[row_index + x..row_index + x + count].fill(true);
+                        }
+                        // opaque
+                        3 => {
+                            pixels[row_index + x..row_index + x + count].copy_from_slice(reader.read_bytes(count)?);
+                            opaque[row_index + x..row_index + x + count].fill(true);
+                            if count % 2 != 0 {
+                                let _ = reader.read_u8()?;
+                            }
+                        }
+                        command => anyhow::bail!("Unknown row command {} in row {}", command, y),
+                    }
+                    x += count;
+                }
+                anyhow::ensure!(reader.position() == row_end, "Row {} has an incorrect length", y);
+                y += 1;
+            }
+            // end sprite
+            5 => break,
+            // transparent rows
+            9 => y += count,
+            command => anyhow::bail!("Unknown sprite command {}", command),
+        }
+    }
+
+    // the transparent color is not stored in the sprite so pick one that the opaque pixels do not use
+    let used_colors: std::collections::HashSet<_> =
+        pixels.iter().zip(&opaque).filter(|(_, opaque)| **opaque).map(|(pixel, _)| *pixel).collect();
+    let transparent_color_index = (0..=255u8).rev().find(|x| !used_colors.contains(x)).unwrap_or(255);
+    for (pixel, opaque) in pixels.iter_mut().zip(&opaque) {
+        if !opaque {
+            *pixel = transparent_color_index;
+        }
+    }
+
+    Ok(Spr1Frame {
+        width,
+        height,
+        transparent_color_index,
+        pixels,
+    })
+}
+
+pub struct Spr2 {
+    pub palette_chunk_id: iff::IffChunkId,
+    pub frames: Vec<Spr2Frame>,
+}
+
+pub struct Spr2Frame {
+    pub width: u16,
+    pub height: u16,
+    pub bounds_left: i16,
+    pub bounds_top: i16,
+    pub palette_chunk_id: iff::IffChunkId,
+    pub transparent_color_index: u8,
+    pub pixels_p: Vec<u8>,
+    pub pixels_z: Vec<u8>,
+    pub pixels_a: Vec<u8>,
+}
+
+impl Spr2Frame {
+    pub fn channel_images(&self) -> (image::GrayImage, image::GrayImage, image::GrayImage) {
+        let width = u32::from(self.width);
+        let height = u32::from(self.height);
+        (
+            image::GrayImage::from_raw(width, height, self.pixels_p.clone()).unwrap(),
+            image::GrayImage::from_raw(width, height, self.pixels_z.clone()).unwrap(),
+            image::GrayImage::from_raw(width, height, self.pixels_a.clone()).unwrap(),
+        )
+    }
+}
+
+pub fn decode_spr2_chunk(chunk: &iff::IffChunk) -> anyhow::Result<Spr2> {
+    let mut reader = iff::IffChunkReader::new(&chunk.data);
+
+    let version = reader.read_u32()?;
+    anyhow::ensure!(
+        version == 1000,
+        error::Error::UnsupportedVersion {
+            chunk_type: *chunk.header.chunk_type(),
+            chunk_id: chunk.header.id(),
+            chunk_label: chunk.header.label(),
+            version,
+        }
+    );
+    let frame_count = reader.read_u32()?;
+    let palette_chunk_id = iff::IffChunkId::new(i16::try_from(reader.read_i32()?)?);
+    let frame_addresses = (0..frame_count).map(|_| reader.read_u32()).collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut frames = Vec::new();
+    for (frame_index, frame_address) in frame_addresses.into_iter().enumerate() {
+        reader.seek(usize::try_from(frame_address).unwrap())?;
+        let frame = decode_spr2_frame(&mut reader).with_context(|| {
+            format!(
+                "Failed to decode frame {} of sprite {} {}",
+                frame_index,
+                chunk.header.id().as_i16(),
+                chunk.header.label()
+            )
+        })?;
+        frames.push(frame);
+    }
+
+    Ok(Spr2 {
+        palette_chunk_id,
+        frames,
+    })
+}
+
+fn decode_spr2_frame(reader: &mut iff::IffChunkReader) -> anyhow::Result<Spr2Frame> {
+    let width = reader.read_u16()?;
+    let height = reader.read_u16()?;
+    let _flags = reader.read_u32()?;
+    let palette_chunk_id = iff::IffChunkId::new(reader.read_i16()?);
+    let transparent_color_index = u8::try_from(reader.read_u16()?)?;
+    let bounds_top = reader.read_i16()?;
+    let bounds_left = reader.read_i16()?;
+
+    let width_usize = usize::from(width);
+    let height_usize = usize::from(height);
+
+    let mut pixels_p = vec![transparent_color_index; width_usize * height_usize];
+    let mut pixels_z = vec![255u8; width_usize * height_usize];
+    let mut pixels_a = vec![0u8; width_usize * height_usize];
+
+    let mut y = 0;
+    loop {
+        let command = reader.read_u16()?;
+        let count = usize::from(command & 0b0001111111111111);
+        match command >> 13 {
+            // start row
+            0 => {
+                anyhow::ensure!(y < height_usize, "Row {} is outside of the frame", y);
+                let row_end = reader.position() - 2 + count;
+                let mut x = 0;
+                while reader.position() < row_end {
+                    let command = reader.read_u16()?;
+                    let count = usize::from(command & 0b0001111111111111);
+                    anyhow::ensure!(x + count <= width_usize, "Row {} is wider than the frame", y);
+                    let row_index = y * width_usize;
+                    match command >> 13 {
+                        // opaque
+                        1 => {
+                            for x in x..x + count {
+                                pixels_z[row_index + x] = reader.read_u8()?;
+                                pixels_p[row_index + x] = reader.read_u8()?;
+                                pixels_a[row_index + x] = 255;
+                            }
+                        }
+                        // translucent
+                        2 => {
+                            for x in x..x + count {
+                                pixels_z[row_index + x] = reader.read_u8()?;
+                                pixels_p[row_index + x] = reader.read_u8()?;
+                                pixels_a[row_index + x] = alpha_5_bit_to_8_bit(reader.read_u8()?);
+                            }
+                            if count % 2 != 0 {
+                                let _ = reader.read_u8()?;
+                            }
+                        }
+                        // transparent
+                        3 => (),
+                        // opaque without depth
+                        6 => {
+                            for x in x..x + count {
+                                pixels_z[row_index + x] = 0;
+                                pixels_p[row_index + x] = reader.read_u8()?;
+                                pixels_a[row_index + x] = 255;
+                            }
+                            if count % 2 != 0 {
+                                let _ = reader.read_u8()?;
+                            }
+                        }
+                        command => anyhow::bail!("Unknown row command {} in row {}", command, y),
+                    }
+                    x += count;
+                }
+                anyhow::ensure!(reader.position() == row_end, "Row {} has an incorrect length", y);
+                y += 1;
+            }
+            // transparent rows
+            4 => y += count,
+            // end
+            5 => break,
+            command => anyhow::bail!("Unknown sprite command {}", command),
+        }
+    }
+
+    Ok(Spr2Frame {
+        width,
+        height,
+        bounds_left,
+        bounds_top,
+        palette_chunk_id,
+        transparent_color_index,
+        pixels_p,
+        pixels_z,
+        pixels_a,
+    })
+}
+
+fn alpha_5_bit_to_8_bit(alpha: u8) -> u8 {
+    let alpha = alpha & 0b00011111;
+    (alpha << 3) | (alpha >> 2)
+}
+
+pub fn read_bmp(
     file_path: &std::path::Path,
 ) -> anyhow::Result<image::codecs::bmp::BmpDecoder<std::io::BufReader<std::fs::File>>> {
     let bmp_buffer =
@@ -697,11 +1051,33 @@ fn read_bmp(
     let bmp = image::codecs::bmp::BmpDecoder::new(bmp_buffer).with_context(|| error::file_read_error(file_path))?;
     anyhow::ensure!(
         bmp.get_palette().is_some(),
-        format!("{} is not an 8-bit bmp", file_path.display())
+        error::Error::NotAn8BitBmp {
+            path: file_path.to_owned()
+        }
     );
     Ok(bmp)
 }
 
+/// Checks that a rectangle read from a sprite channel image lies inside of it.
+pub fn check_rect_bounds(
+    file_path: &std::path::Path,
+    (x, y, width, height): (u32, u32, u32, u32),
+    (image_width, image_height): (u32, u32),
+) -> Result<(), error::Error> {
+    if x + width <= image_width && y + height <= image_height {
+        return Ok(());
+    }
+    Err(error::Error::RectOutsideOfImage {
+        path: file_path.to_owned(),
+        x,
+        y,
+        width,
+        height,
+        image_width,
+        image_height,
+    })
+}
+
 fn read_bmp_rect(
     bmp: &mut image::codecs::bmp::BmpDecoder<std::io::BufReader<std::fs::File>>,
     x: u32,
@@ -716,6 +1092,27 @@ fn read_bmp_rect(
     Ok(pixels)
 }
 
+fn read_channel_rect(
+    split_sprites: &sprite::SplitSprites,
+    file_path: &std::path::Path,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> anyhow::Result<Vec<u8>> {
+    let Some(channel_image) = split_sprites.channel_images.get(file_path) else {
+        let mut bmp = read_bmp(file_path)?;
+        use image::ImageDecoder;
+        check_rect_bounds(file_path, (x, y, width, height), bmp.dimensions())?;
+        return read_bmp_rect(&mut bmp, x, y, width, height).with_context(|| error::file_read_error(file_path));
+    };
+
+    let image = &channel_image.image;
+    check_rect_bounds(file_path, (x, y, width, height), image.dimensions())?;
+    use image::GenericImageView;
+    Ok(image.view(x, y, width, height).pixels().map(|(_, _, pixel)| pixel[0]).collect())
+}
+
 pub fn deserialize_sprites<'de, D>(deserializer: D) -> Result<iff_description::Sprites, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -816,3 +1213,132 @@ where
 
     Ok(sprites)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palt;
+
+    #[test]
+    fn spr1_runs_after_a_short_repeat_keep_their_pixels() {
+        let source_directory = std::env::temp_dir().join(format!("ts1-compiler-spr1-{}", std::process::id()));
+        std::fs::create_dir_all(&source_directory).unwrap();
+        let pixels = image::GrayImage::from_raw(5, 1, vec![255, 5, 5, 1, 2]).unwrap();
+        sprite::write_bmp_with_palette(&source_directory.join("z.bmp"), &pixels, &palt::grayscale_palette()).unwrap();
+
+        let frame = SpriteFrame::new_spr1(
+            0,
+            sprite::ZoomLevel::Zero,
+            sprite::Rotation::NorthWest,
+            5,
+            1,
+            iff::IffChunkId::new(1),
+            255,
+            std::path::Path::new("z.bmp"),
+        );
+        let sprite = Sprite::new_spr1(
+            "test",
+            iff::IffChunkId::new(1),
+            iff::IffChunkId::new(1),
+            false,
+            vec![frame],
+        );
+        let chunk = sprite.to_spr1_chunk(&source_directory);
+        std::fs::remove_dir_all(&source_directory).unwrap();
+
+        // reading the color channel failed because spr# frames only have a depth channel, and the opaque run was
+        // written as 5 5 5 5 because the pixels after the repeat were read from the start of the run
+        #[rustfmt::skip]
+        let expected_data = [
+            // version, frame count, palette ID, frame address
+            248, 1, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 16, 0, 0, 0,
+            // frame header, height, width
+            0, 0, 0, 0, 1, 0, 5, 0,
+            // start sprite, start row, transparent, opaque, end sprite
+            0, 0, 4, 10, 1, 1, 3, 4, 5, 5, 1, 2, 5, 0,
+        ];
+        assert_eq!(chunk.unwrap().data, expected_data);
+    }
+
+    #[test]
+    fn spr2_frames_survive_a_round_trip() {
+        const TRANSPARENT_COLOR_INDEX: u8 = 0;
+        // a transparent first row, then opaque, translucent and transparent runs
+        #[rustfmt::skip]
+        let (pixels_p, pixels_z, pixels_a) = (
+            [
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 7, 8, 9, 0, 6, 0,
+                0, 0, 0, 5, 5, 5, 0, 0,
+            ],
+            [
+                255, 255, 255, 255, 255, 255, 255, 255,
+                255, 255, 255, 255, 255, 255, 255, 255,
+                255, 255, 10, 20, 30, 255, 40, 255,
+                255, 255, 255, 50, 60, 70, 255, 255,
+            ],
+            [
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 255, 255, 132, 0, 255, 0,
+                0, 0, 0, 132, 132, 255, 0, 0,
+            ],
+        );
+
+        let mut split_sprites = sprite::SplitSprites::default();
+        for (file_name, pixels) in [("p.bmp", pixels_p), ("z.bmp", pixels_z), ("a.bmp", pixels_a)] {
+            split_sprites.channel_images.insert(
+                std::path::PathBuf::from(file_name),
+                sprite::SpriteChannelImage {
+                    image: image::GrayImage::from_raw(8, 4, pixels.to_vec()).unwrap(),
+                    palette: palt::grayscale_palette(),
+                },
+            );
+        }
+
+        let sprite_image_description = sprite::SpriteImageDescription {
+            width: 8,
+            height: 4,
+            bounds: sprite::SpriteBounds {
+                left: 2,
+                top: 1,
+                right: 7,
+                bottom: 4,
+            },
+            offsets: sprite::SpriteOffsets {
+                x: 0,
+                y: 0,
+                x_flipped: 0,
+            },
+            palette_id: iff::IffChunkId::new(3),
+            transparent_color_index: TRANSPARENT_COLOR_INDEX,
+        };
+        let frame = SpriteFrame::new(
+            0,
+            sprite::ZoomLevel::Zero,
+            sprite::Rotation::NorthWest,
+            &sprite_image_description,
+            std::path::Path::new("p.bmp"),
+            std::path::Path::new("z.bmp"),
+            std::path::Path::new("a.bmp"),
+        );
+        let sprite = Sprite::new("test", iff::IffChunkId::new(1), iff::IffChunkId::new(3), vec![frame]);
+
+        let chunk = sprite.to_chunk(std::path::Path::new(""), &split_sprites).unwrap();
+        let spr2 = decode_spr2_chunk(&chunk).unwrap();
+
+        assert_eq!(spr2.palette_chunk_id, iff::IffChunkId::new(3));
+        assert_eq!(spr2.frames.len(), 1);
+        let frame = &spr2.frames[0];
+        assert_eq!((frame.width, frame.height), (5, 3));
+        assert_eq!((frame.bounds_left, frame.bounds_top), (2, 1));
+        assert_eq!(frame.palette_chunk_id, iff::IffChunkId::new(3));
+        assert_eq!(frame.transparent_color_index, TRANSPARENT_COLOR_INDEX);
+
+        let crop = |pixels: &[u8]| -> Vec<u8> { (1..4).flat_map(|y| pixels[y * 8 + 2..y * 8 + 7].to_vec()).collect() };
+        assert_eq!(frame.pixels_p, crop(&pixels_p));
+        assert_eq!(frame.pixels_z, crop(&pixels_z));
+        assert_eq!(frame.pixels_a, crop(&pixels_a));
+    }
+}
@@ -78,7 +78,7 @@ impl std::fmt::Display for Channel {
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct SpriteBounds {
     pub left: i16,
     pub top: i16,
@@ -86,14 +86,14 @@ pub struct SpriteBounds {
     pub bottom: i16,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct SpriteOffsets {
     pub x: i32,
     pub y: i32,
     pub x_flipped: i32,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct SpriteImageDescription {
     pub width: i16,
     pub height: i16,
@@ -103,6 +103,17 @@ pub struct SpriteImageDescription {
     pub transparent_color_index: u8,
 }
 
+pub fn sprite_dimensions(zoom_level: ZoomLevel) -> (i32, i32) {
+    const SPRITE_WIDTH: i32 = 136;
+    const SPRITE_HEIGHT: i32 = 384;
+
+    match zoom_level {
+        ZoomLevel::Zero => (SPRITE_WIDTH, SPRITE_HEIGHT),
+        ZoomLevel::One => (SPRITE_WIDTH / 2, SPRITE_HEIGHT / 2),
+        ZoomLevel::Two => (SPRITE_WIDTH / 4, SPRITE_HEIGHT / 4),
+    }
+}
+
 pub fn sprite_channel_file_path(
     sprite_frame_directory: &std::path::Path,
     zoom_level: ZoomLevel,
@@ -143,14 +154,63 @@ pub fn write_sprite_image_description_file(
     rotation: Rotation,
 ) -> anyhow::Result<()> {
     let sprite_image_description_file_path = sprite_description_file_path(sprite_frame_directory, zoom_level, rotation);
+    write_sprite_image_description(sprite_image_description, &sprite_image_description_file_path)
+}
+
+fn write_sprite_image_description(
+    sprite_image_description: &SpriteImageDescription,
+    sprite_image_description_file_path: &std::path::Path,
+) -> anyhow::Result<()> {
     let json_string = serde_json::to_string_pretty(&sprite_image_description).with_context(|| {
         format!(
             "Failed to serialize json file {}",
             sprite_image_description_file_path.display()
         )
     })?;
-    std::fs::write(&sprite_image_description_file_path, json_string)
-        .with_context(|| error::file_write_error(&sprite_image_description_file_path))
+    std::fs::write(sprite_image_description_file_path, json_string)
+        .with_context(|| error::file_write_error(sprite_image_description_file_path))
+}
+
+pub fn write_bmp_with_palette(
+    file_path: &std::path::Path,
+    sprite: &image::GrayImage,
+    palette: &[[u8; 3]],
+) -> anyhow::Result<()> {
+    let mut output_buffer = Vec::new();
+    let mut encoder = image::codecs::bmp::BmpEncoder::new(&mut output_buffer);
+    encoder
+        .encode_with_palette(
+            sprite.as_raw(),
+            sprite.width(),
+            sprite.height(),
+            image::ExtendedColorType::L8,
+            Some(palette),
+        )
+        .unwrap();
+
+    let mut file = std::fs::File::create(file_path).with_context(|| error::file_write_error(file_path))?;
+    use std::io::Write;
+    file.write_all(&output_buffer).with_context(|| error::file_write_error(file_path))
+}
+
+pub fn calculate_sprite_offsets(sprite_width: u32, bounds: &SpriteBounds, zoom_level: ZoomLevel) -> SpriteOffsets {
+    let left_bound_flipped = i32::try_from(sprite_width).unwrap() - i32::from(bounds.right);
+    const SPRITE_CENTER_X: i32 = 68;
+    const SPRITE_CENTER_Y: i32 = 348;
+    let (sprite_center_x, sprite_center_y) = match zoom_level {
+        ZoomLevel::Zero => (SPRITE_CENTER_X, SPRITE_CENTER_Y),
+        ZoomLevel::One => (SPRITE_CENTER_X / 2, SPRITE_CENTER_Y / 2),
+        ZoomLevel::Two => (SPRITE_CENTER_X / 4, SPRITE_CENTER_Y / 4),
+    };
+    let offset_x = 0 - (sprite_center_x - i32::from(bounds.left));
+    let offset_y = 0 - (sprite_center_y - i32::from(bounds.bottom));
+    let offset_x_flipped = 0 - (sprite_center_x - left_bound_flipped);
+
+    SpriteOffsets {
+        x: offset_x,
+        y: offset_y,
+        x_flipped: offset_x_flipped,
+    }
 }
 
 pub fn calculate_sprite_image_description(
@@ -228,33 +288,126 @@ pub fn calculate_sprite_image_description(
         bounds_bottom + 1
     };
 
-    let left_bound_flipped = i32::try_from(alpha_sprite.width()).unwrap() - i32::try_from(bounds_right).unwrap();
-    const SPRITE_CENTER_X: i32 = 68;
-    const SPRITE_CENTER_Y: i32 = 348;
-    let (sprite_center_x, sprite_center_y) = match zoom_level {
-        ZoomLevel::Zero => (SPRITE_CENTER_X, SPRITE_CENTER_Y),
-        ZoomLevel::One => (SPRITE_CENTER_X / 2, SPRITE_CENTER_Y / 2),
-        ZoomLevel::Two => (SPRITE_CENTER_X / 4, SPRITE_CENTER_Y / 4),
+    let bounds = SpriteBounds {
+        left: i16::try_from(bounds_left).unwrap(),
+        top: i16::try_from(bounds_top).unwrap(),
+        right: i16::try_from(bounds_right).unwrap(),
+        bottom: i16::try_from(bounds_bottom).unwrap(),
     };
-    let offset_x = 0 - (sprite_center_x - i32::try_from(bounds_left).unwrap());
-    let offset_y = 0 - (sprite_center_y - i32::try_from(bounds_bottom).unwrap());
-    let offset_x_flipped = 0 - (sprite_center_x - left_bound_flipped);
+    let offsets = calculate_sprite_offsets(alpha_sprite.width(), &bounds, zoom_level);
 
     SpriteImageDescription {
         width: alpha_sprite.width().try_into().unwrap(),
         height: alpha_sprite.height().try_into().unwrap(),
-        bounds: SpriteBounds {
-            left: i16::try_from(bounds_left).unwrap(),
-            top: i16::try_from(bounds_top).unwrap(),
-            right: i16::try_from(bounds_right).unwrap(),
-            bottom: i16::try_from(bounds_bottom).unwrap(),
-        },
-        offsets: SpriteOffsets {
-            x: offset_x,
-            y: offset_y,
-            x_flipped: offset_x_flipped,
-        },
+        bounds,
+        offsets,
         palette_id,
         transparent_color_index,
     }
 }
+
+/// A split sprite channel held in memory instead of in an 8-bit bmp file.
+pub struct SpriteChannelImage {
+    pub image: image::GrayImage,
+    pub palette: Vec<[u8; 3]>,
+}
+
+/// The split sprites of an object held in memory, keyed by the paths that `split` writes them to.
+///
+/// Reads fall back to the files on disk for anything that is not held in memory.
+#[derive(Default)]
+pub struct SplitSprites {
+    /// The sprite ID of every tile directory.
+    pub sprite_ids: std::collections::BTreeMap<std::path::PathBuf, iff::IffChunkId>,
+    pub channel_images: std::collections::HashMap<std::path::PathBuf, SpriteChannelImage>,
+    pub image_descriptions: std::collections::HashMap<std::path::PathBuf, SpriteImageDescription>,
+}
+
+impl SplitSprites {
+    /// Reads the sprite IDs and image descriptions of the tiles in a split sprites directory, but not the images.
+    pub fn read_descriptions(split_sprites_directory: &std::path::Path) -> anyhow::Result<SplitSprites> {
+        let mut split_sprites = SplitSprites::default();
+        let entries = std::fs::read_dir(split_sprites_directory)
+            .with_context(|| format!("Failed to read directory {}", split_sprites_directory.display()))?;
+        for entry in entries {
+            let tile_directory = entry
+                .with_context(|| format!("Failed to read directory {}", split_sprites_directory.display()))?
+                .path();
+            if !tile_directory.is_dir() {
+                continue;
+            }
+
+            let sprite_id_file_path = tile_directory.join("sprite id").with_extension("json");
+            let json_string = std::fs::read_to_string(&sprite_id_file_path)
+                .with_context(|| error::file_read_error(&sprite_id_file_path))?;
+            let sprite_id = serde_json::from_str::<iff::IffChunkId>(&json_string)
+                .with_context(|| format!("Failed to deserialize json file {}", sprite_id_file_path.display()))?;
+
+            for zoom_level in [ZoomLevel::Zero, ZoomLevel::One, ZoomLevel::Two] {
+                for rotation in [
+                    Rotation::NorthWest,
+                    Rotation::NorthEast,
+                    Rotation::SouthEast,
+                    Rotation::SouthWest,
+                ] {
+                    let description_file_path = sprite_description_file_path(&tile_directory, zoom_level, rotation);
+                    if description_file_path.is_file() {
+                        let description = read_sprite_image_description_file(&description_file_path)?;
+                        split_sprites.image_descriptions.insert(description_file_path, description);
+                    }
+                }
+            }
+            split_sprites.sprite_ids.insert(tile_directory, sprite_id);
+        }
+        Ok(split_sprites)
+    }
+
+    /// Moves the split sprites of another split into this one.
+    pub fn extend(&mut self, split_sprites: SplitSprites) {
+        self.sprite_ids.extend(split_sprites.sprite_ids);
+        self.channel_images.extend(split_sprites.channel_images);
+        self.image_descriptions.extend(split_sprites.image_descriptions);
+    }
+
+    /// Writes every split sprite to the files it is keyed by.
+    pub fn write(&self) -> anyhow::Result<()> {
+        for (tile_directory, sprite_id) in &self.sprite_ids {
+            std::fs::create_dir_all(tile_directory)
+                .with_context(|| format!("Failed to create directory {}", tile_directory.display()))?;
+
+            let sprite_id_file_path = tile_directory.join("sprite id").with_extension("json");
+            let json_string = serde_json::to_string_pretty(sprite_id)
+                .with_context(|| format!("Failed to serialize json file {}", sprite_id_file_path.display()))?;
+            std::fs::write(&sprite_id_file_path, json_string)
+                .with_context(|| error::file_write_error(&sprite_id_file_path))?;
+        }
+        for (file_path, channel_image) in &self.channel_images {
+            write_bmp_with_palette(file_path, &channel_image.image, &channel_image.palette)?;
+        }
+        for (file_path, image_description) in &self.image_descriptions {
+            write_sprite_image_description(image_description, file_path)?;
+        }
+        Ok(())
+    }
+
+    /// The image description at the path, or `None` if there is no such description.
+    pub fn read_image_description(
+        &self,
+        file_path: &std::path::Path,
+    ) -> anyhow::Result<Option<SpriteImageDescription>> {
+        if let Some(image_description) = self.image_descriptions.get(file_path) {
+            return Ok(Some(image_description.clone()));
+        }
+        if !file_path.is_file() {
+            return Ok(None);
+        }
+        read_sprite_image_description_file(file_path).map(Some)
+    }
+
+    pub fn read_channel_image(&self, file_path: &std::path::Path) -> anyhow::Result<image::GrayImage> {
+        if let Some(channel_image) = self.channel_images.get(file_path) {
+            return Ok(channel_image.image.clone());
+        }
+        Ok(image::open(file_path).with_context(|| error::file_read_error(file_path))?.to_luma8())
+    }
+}
@@ -0,0 +1,214 @@
+use crate::error;
+use crate::iff;
+use crate::message;
+use crate::objd;
+use crate::palt;
+use crate::spr;
+use crate::sprite;
+
+use anyhow::Context;
+
+#[derive(serde::Serialize)]
+struct ExportedFrameDescription {
+    width: u16,
+    height: u16,
+    bounds_left: i16,
+    bounds_top: i16,
+    palette_id: iff::IffChunkId,
+    transparent_color_index: u8,
+}
+
+fn exported_frame_file_path(
+    sprite_directory: &std::path::Path,
+    frame_index: usize,
+    suffix: &str,
+) -> std::path::PathBuf {
+    sprite_directory.join(format!("{frame_index} {suffix}"))
+}
+
+fn write_frame_description(
+    sprite_directory: &std::path::Path,
+    frame_index: usize,
+    frame_description: &ExportedFrameDescription,
+) -> anyhow::Result<()> {
+    let frame_description_file_path =
+        exported_frame_file_path(sprite_directory, frame_index, "description").with_extension("json");
+    let json_string = serde_json::to_string_pretty(frame_description).with_context(|| {
+        format!(
+            "Failed to serialize json file {}",
+            frame_description_file_path.display()
+        )
+    })?;
+    std::fs::write(&frame_description_file_path, json_string)
+        .with_context(|| error::file_write_error(&frame_description_file_path))
+}
+
+fn create_sprite_directory(
+    output_directory: &std::path::Path,
+    chunk: &iff::IffChunk,
+) -> anyhow::Result<std::path::PathBuf> {
+    let sprite_directory = output_directory.join(iff::chunk_file_name(chunk.header.id(), &chunk.header.label()));
+    std::fs::create_dir_all(&sprite_directory)
+        .with_context(|| format!("Failed to create directory {}", sprite_directory.display()))?;
+    Ok(sprite_directory)
+}
+
+fn export_spr1(
+    output_directory: &std::path::Path,
+    chunk: &iff::IffChunk,
+    palettes: &std::collections::HashMap<iff::IffChunkId, Vec<[u8; 3]>>,
+    is_custom_wall_style: bool,
+) -> anyhow::Result<()> {
+    let chunk_id = chunk.header.id();
+    let chunk_label = chunk.header.label();
+    let spr1 = spr::decode_spr1_chunk(chunk)?;
+
+    let sprite_directory = create_sprite_directory(output_directory, chunk)?;
+
+    // custom wall style sprites are masks with pixels of either 0 or 255 and do not use a palette
+    let palette = if is_custom_wall_style {
+        palt::grayscale_palette()
+    } else {
+        match palettes.get(&spr1.palette_chunk_id) {
+            Some(palette) => palette.clone(),
+            None => {
+                message::warning(
+                    &format!(
+                        "Palette {} of sprite {} {} was not found, the sprite will be grayscale",
+                        spr1.palette_chunk_id.as_i16(),
+                        chunk_id.as_i16(),
+                        chunk_label
+                    ),
+                    Some(&message::ChunkReference::new("SPR#", chunk_id, &chunk_label)),
+                );
+                palt::grayscale_palette()
+            }
+        }
+    };
+
+    for (frame_index, frame) in spr1.frames.iter().enumerate() {
+        let frame_description = ExportedFrameDescription {
+            width: frame.width,
+            height: frame.height,
+            bounds_left: 0,
+            bounds_top: 0,
+            palette_id: spr1.palette_chunk_id,
+            transparent_color_index: frame.transparent_color_index,
+        };
+        write_frame_description(&sprite_directory, frame_index, &frame_description)?;
+
+        if frame.width == 0 || frame.height == 0 {
+            continue;
+        }
+
+        let sprite_z_file_path =
+            exported_frame_file_path(&sprite_directory, frame_index, &sprite::Channel::Depth.to_string())
+                .with_extension("bmp");
+        sprite::write_bmp_with_palette(&sprite_z_file_path, &frame.image(), &palette)?;
+    }
+
+    Ok(())
+}
+
+fn export_spr2(
+    output_directory: &std::path::Path,
+    chunk: &iff::IffChunk,
+    palettes: &std::collections::HashMap<iff::IffChunkId, Vec<[u8; 3]>>,
+) -> anyhow::Result<()> {
+    let chunk_id = chunk.header.id();
+    let chunk_label = chunk.header.label();
+    let spr2 = spr::decode_spr2_chunk(chunk)?;
+
+    let sprite_directory = create_sprite_directory(output_directory, chunk)?;
+
+    for (frame_index, frame) in spr2.frames.iter().enumerate() {
+        let frame_description = ExportedFrameDescription {
+            width: frame.width,
+            height: frame.height,
+            bounds_left: frame.bounds_left,
+            bounds_top: frame.bounds_top,
+            palette_id: frame.palette_chunk_id,
+            transparent_color_index: frame.transparent_color_index,
+        };
+        write_frame_description(&sprite_directory, frame_index, &frame_description)?;
+
+        // empty frames have no pixels to write
+        if frame.width == 0 || frame.height == 0 {
+            continue;
+        }
+
+        let palette = match palettes.get(&frame.palette_chunk_id) {
+            Some(palette) => palette.clone(),
+            None => {
+                message::warning(
+                    &format!(
+                        "Palette {} of frame {} in sprite {} {} was not found, the color channel will be grayscale",
+                        frame.palette_chunk_id.as_i16(),
+                        frame_index,
+                        chunk_id.as_i16(),
+                        chunk_label
+                    ),
+                    Some(&message::ChunkReference::new("SPR2", chunk_id, &chunk_label)),
+                );
+                palt::grayscale_palette()
+            }
+        };
+
+        let (sprite_p, sprite_z, sprite_a) = frame.channel_images();
+
+        let sprite_p_file_path =
+            exported_frame_file_path(&sprite_directory, frame_index, &sprite::Channel::Color.to_string())
+                .with_extension("bmp");
+        let sprite_z_file_path =
+            exported_frame_file_path(&sprite_directory, frame_index, &sprite::Channel::Depth.to_string())
+                .with_extension("bmp");
+        let sprite_a_file_path =
+            exported_frame_file_path(&sprite_directory, frame_index, &sprite::Channel::Alpha.to_string())
+                .with_extension("bmp");
+
+        sprite::write_bmp_with_palette(&sprite_p_file_path, &sprite_p, &palette)?;
+        sprite_z.save(&sprite_z_file_path).with_context(|| error::file_write_error(&sprite_z_file_path))?;
+        sprite_a.save(&sprite_a_file_path).with_context(|| error::file_write_error(&sprite_a_file_path))?;
+    }
+
+    Ok(())
+}
+
+pub fn export_sprites(iff_file_path: &std::path::Path, output_directory: &std::path::Path) -> anyhow::Result<()> {
+    let iff = iff::read_iff_file(iff_file_path)?;
+    let palettes = palt::read_palt_chunks(&iff)?;
+
+    let mut custom_wall_style_sprite_ids = std::collections::HashSet::new();
+    for chunk in iff.chunks.iter().filter(|x| x.header.chunk_type() == b"OBJD") {
+        let object_definition = objd::ObjectDefinition::from_chunk(chunk).with_context(|| {
+            format!(
+                "Failed to decode object definition {} {}",
+                chunk.header.id().as_i16(),
+                chunk.header.label()
+            )
+        })?;
+        custom_wall_style_sprite_ids.extend(object_definition.custom_wall_style_sprite_id());
+    }
+
+    for chunk in &iff.chunks {
+        let result = match chunk.header.chunk_type() {
+            b"SPR#" => export_spr1(
+                output_directory,
+                chunk,
+                &palettes,
+                custom_wall_style_sprite_ids.contains(&chunk.header.id()),
+            ),
+            b"SPR2" => export_spr2(output_directory, chunk, &palettes),
+            _ => continue,
+        };
+        result.with_context(|| {
+            format!(
+                "Failed to export sprite {} {}",
+                chunk.header.id().as_i16(),
+                chunk.header.label()
+            )
+        })?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use crate::iff;
+use crate::windows_1252;
+
+use anyhow::Context;
+
+const STRING_TABLE_FORMAT_PASCAL: i16 = 0;
+const STRING_TABLE_FORMAT_NULL_TERMINATED: i16 = -1;
+const STRING_TABLE_FORMAT_PAIRS: i16 = -2;
+const STRING_TABLE_FORMAT_MULTI_LANGUAGE: i16 = -3;
+const STRING_TABLE_FORMAT_LANGUAGE_SETS: i16 = -4;
+
+// language codes start at 1 for US English
+const LANGUAGE_US_ENGLISH: u8 = 1;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StringTable {
+    #[serde(rename = "@name")]
+    pub chunk_label: String,
+    #[serde(rename = "@id")]
+    pub chunk_id: iff::IffChunkId,
+    #[serde(default, rename = "string")]
+    pub strings: Vec<StringTableEntry>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StringTableEntry {
+    #[serde(rename = "@language")]
+    pub language_code: u8,
+    #[serde(rename = "@value")]
+    pub value: String,
+    #[serde(default, rename = "@comment")]
+    pub comment: String,
+}
+
+fn read_null_terminated_string(reader: &mut iff::IffChunkReader) -> anyhow::Result<String> {
+    let mut bytes = Vec::new();
+    loop {
+        match reader.read_u8()? {
+            0 => break,
+            x => bytes.push(x),
+        }
+    }
+    Ok(windows_1252::decode(&bytes))
+}
+
+fn read_pascal_string(reader: &mut iff::IffChunkReader) -> anyhow::Result<String> {
+    let length = reader.read_u8()?;
+    Ok(windows_1252::decode(reader.read_bytes(usize::from(length))?))
+}
+
+fn read_variable_length_pascal_string(reader: &mut iff::IffChunkReader) -> anyhow::Result<String> {
+    // the length is stored 7 bits at a time with the high bit set when another byte follows
+    let mut length = 0usize;
+    for shift in (0..32).step_by(7) {
+        let byte = reader.read_u8()?;
+        length |= usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    Ok(windows_1252::decode(reader.read_bytes(length)?))
+}
+
+fn write_null_terminated_string(data: &mut Vec<u8>, string: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !string.contains('\0'),
+        "String \"{}\" contains a null character",
+        string
+    );
+    data.extend_from_slice(&windows_1252::encode(string)?);
+    data.push(0);
+    Ok(())
+}
+
+impl StringTable {
+    pub fn from_chunk(chunk: &iff::IffChunk) -> anyhow::Result<StringTable> {
+        let mut reader = iff::IffChunkReader::new(&chunk.data);
+
+        let format = reader.read_i16()?;
+        let mut strings = Vec::new();
+        match format {
+            STRING_TABLE_FORMAT_PASCAL | STRING_TABLE_FORMAT_NULL_TERMINATED | STRING_TABLE_FORMAT_PAIRS => {
+                let string_count = reader.read_u16()?;
+                for _ in 0..string_count {
+                    let (value, comment) = match format {
+                        STRING_TABLE_FORMAT_PASCAL => (read_pascal_string(&mut reader)?, String::new()),
+                        STRING_TABLE_FORMAT_NULL_TERMINATED => {
+                            (read_null_terminated_string(&mut reader)?, String::new())
+                        }
+                        _ => (
+                            read_null_terminated_string(&mut reader)?,
+                            read_null_terminated_string(&mut reader)?,
+                        ),
+                    };
+                    strings.push(StringTableEntry {
+                        language_code: LANGUAGE_US_ENGLISH,
+                        value,
+                        comment,
+                    });
+                }
+            }
+            STRING_TABLE_FORMAT_MULTI_LANGUAGE => {
+                let string_count = reader.read_u16()?;
+                for _ in 0..string_count {
+                    strings.push(StringTableEntry {
+                        language_code: reader.read_u8()?,
+                        value: read_null_terminated_string(&mut reader)?,
+                        comment: read_null_terminated_string(&mut reader)?,
+                    });
+                }
+            }
+            STRING_TABLE_FORMAT_LANGUAGE_SETS => {
+                let language_set_count = reader.read_u8()?;
+                for language_set_index in 0..language_set_count {
+                    let string_count = reader.read_u16()?;
+                    for _ in 0..string_count {
+                        let _ = reader.read_u8()?;
+                        strings.push(StringTableEntry {
+                            language_code: language_set_index + 1,
+                            value: read_variable_length_pascal_string(&mut reader)?,
+                            comment: read_variable_length_pascal_string(&mut reader)?,
+                        });
+                    }
+                }
+            }
+            format => anyhow::bail!(
+                "Unknown string table format {} in {} {} {}",
+                format,
+                String::from_utf8_lossy(chunk.header.chunk_type()),
+                chunk.header.id().as_i16(),
+                chunk.header.label()
+            ),
+        }
+
+        Ok(StringTable {
+            chunk_label: chunk.header.label(),
+            chunk_id: chunk.header.id(),
+            strings,
+        })
+    }
+
+    pub fn to_chunk(&self, chunk_type: &[u8; 4]) -> anyhow::Result<iff::IffChunk> {
+        let mut data = Vec::new();
+
+        // always written in the multi language format, the languages are kept in the order of the description
+        data.extend_from_slice(&STRING_TABLE_FORMAT_MULTI_LANGUAGE.to_le_bytes());
+        let string_count = u16::try_from(self.strings.len()).with_context(|| {
+            format!(
+                "String table {} {} has more than {} strings",
+                self.chunk_id.as_i16(),
+                self.chunk_label,
+                u16::MAX
+            )
+        })?;
+        data.extend_from_slice(&string_count.to_le_bytes());
+
+        for string in &self.strings {
+            anyhow::ensure!(
+                string.language_code != 0,
+                "String table {} {} contains a string with language code 0",
+                self.chunk_id.as_i16(),
+                self.chunk_label
+            );
+            data.push(string.language_code);
+            write_null_terminated_string(&mut data, &string.value)?;
+            write_null_terminated_string(&mut data, &string.comment)?;
+        }
+
+        let header = iff::IffChunkHeader::new(chunk_type, data.len(), self.chunk_id, &self.chunk_label)?;
+
+        Ok(iff::IffChunk { header, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_ascii_strings_survive_a_round_trip() {
+        let string_table = StringTable {
+            chunk_label: "Café".to_owned(),
+            chunk_id: iff::IffChunkId::new(128),
+            strings: vec![StringTableEntry {
+                language_code: LANGUAGE_US_ENGLISH,
+                value: "Élégant sofa – 5€".to_owned(),
+                comment: "naïve".to_owned(),
+            }],
+        };
+
+        let chunk = string_table.to_chunk(b"STR#").unwrap();
+        assert!(chunk.data.windows(2).any(|x| x == [0xc9, b'l']));
+
+        let decoded_string_table = StringTable::from_chunk(&chunk).unwrap();
+        assert_eq!(decoded_string_table.chunk_label, "Café");
+        assert_eq!(decoded_string_table.strings[0].value, "Élégant sofa – 5€");
+        assert_eq!(decoded_string_table.strings[0].comment, "naïve");
+    }
+
+    #[test]
+    fn strings_outside_of_windows_1252_are_rejected() {
+        let string_table = StringTable {
+            chunk_label: String::new(),
+            chunk_id: iff::IffChunkId::new(128),
+            strings: vec![StringTableEntry {
+                language_code: LANGUAGE_US_ENGLISH,
+                value: "沙发".to_owned(),
+                comment: String::new(),
+            }],
+        };
+        assert!(string_table.to_chunk(b"STR#").is_err());
+    }
+}
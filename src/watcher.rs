@@ -0,0 +1,168 @@
+use ts1_compiler::compiler;
+use ts1_compiler::iff;
+use ts1_compiler::message;
+use ts1_compiler::splitter;
+use ts1_compiler::xml_updater;
+
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+// blender writes the renders one file at a time so wait for the writes to settle before rebuilding
+const DEBOUNCE_DURATION: std::time::Duration = std::time::Duration::from_millis(1500);
+
+type Snapshot = std::collections::BTreeMap<std::path::PathBuf, (Option<std::time::SystemTime>, u64)>;
+
+// the stages are ordered so that the earliest affected stage also runs every stage after it
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    Split,
+    Compile,
+}
+
+struct WatchedPaths {
+    full_sprites_directory: std::path::PathBuf,
+    object_description_file_path: std::path::PathBuf,
+    xml_file_path: std::path::PathBuf,
+}
+
+struct Snapshots {
+    sprite_sources: Snapshot,
+    xml: Snapshot,
+}
+
+fn add_file_to_snapshot(file_path: &std::path::Path, snapshot: &mut Snapshot) {
+    // files that are missing or being written are picked up by a later poll
+    if let Ok(metadata) = std::fs::metadata(file_path) {
+        snapshot.insert(file_path.to_owned(), (metadata.modified().ok(), metadata.len()));
+    }
+}
+
+fn add_directory_to_snapshot(directory: &std::path::Path, snapshot: &mut Snapshot) {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            add_directory_to_snapshot(&path, snapshot);
+        } else {
+            add_file_to_snapshot(&path, snapshot);
+        }
+    }
+}
+
+impl WatchedPaths {
+    fn snapshot(&self) -> Snapshots {
+        let mut sprite_sources = Snapshot::new();
+        add_directory_to_snapshot(&self.full_sprites_directory, &mut sprite_sources);
+        add_file_to_snapshot(&self.object_description_file_path, &mut sprite_sources);
+
+        let mut xml = Snapshot::new();
+        add_file_to_snapshot(&self.xml_file_path, &mut xml);
+
+        Snapshots { sprite_sources, xml }
+    }
+}
+
+fn changed_stage(old: &Snapshots, new: &Snapshots) -> Option<Stage> {
+    if old.sprite_sources != new.sprite_sources {
+        Some(Stage::Split)
+    } else if old.xml != new.xml {
+        Some(Stage::Compile)
+    } else {
+        None
+    }
+}
+
+fn rebuild(
+    the_sims_directory: &std::path::Path,
+    source_directory: &std::path::Path,
+    object_name: &str,
+    variant: Option<&str>,
+    stage: Stage,
+    job_count: usize,
+    options: iff::RebuildOptions,
+) -> anyhow::Result<()> {
+    let xml_file_path = source_directory.join(object_name).with_extension("xml");
+    if stage <= Stage::Split {
+        message::info("Splitting sprites");
+        splitter::split(source_directory, object_name, variant, job_count)?;
+        message::info(&format!("Updating {}", xml_file_path.display()));
+        xml_updater::update(source_directory, object_name, variant)?;
+    }
+    message::info(&format!("Compiling {}", xml_file_path.display()));
+    compiler::compile(the_sims_directory, &xml_file_path, options)
+}
+
+/// Watches the full sprites, the object description and the xml file of an object and rebuilds the object whenever
+/// they change. Only the stages after the earliest changed source are run again.
+pub fn watch(
+    the_sims_directory: &std::path::Path,
+    source_directory: &std::path::Path,
+    object_name: &str,
+    variant: Option<&str>,
+    job_count: usize,
+    options: iff::RebuildOptions,
+) -> anyhow::Result<()> {
+    let variant_object_name = if let Some(variant) = variant {
+        format!("{} - {}", object_name, variant)
+    } else {
+        object_name.to_owned()
+    };
+    let watched_paths = WatchedPaths {
+        full_sprites_directory: source_directory.join(format!("{} - full sprites", variant_object_name)),
+        object_description_file_path: source_directory
+            .join(format!("{} - object description", object_name))
+            .with_extension("json"),
+        xml_file_path: source_directory.join(object_name).with_extension("xml"),
+    };
+    anyhow::ensure!(
+        watched_paths.xml_file_path.is_file(),
+        "Failed to find xml file {}",
+        watched_paths.xml_file_path.display()
+    );
+
+    message::info(&format!(
+        "Watching {}, {} and {}",
+        watched_paths.full_sprites_directory.display(),
+        watched_paths.object_description_file_path.display(),
+        watched_paths.xml_file_path.display()
+    ));
+
+    let mut snapshots = watched_paths.snapshot();
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let mut latest_snapshots = watched_paths.snapshot();
+        let Some(mut stage) = changed_stage(&snapshots, &latest_snapshots) else {
+            continue;
+        };
+
+        // keep waiting while files are still being written
+        let mut last_change_time = std::time::Instant::now();
+        while last_change_time.elapsed() < DEBOUNCE_DURATION {
+            std::thread::sleep(POLL_INTERVAL);
+            let new_snapshots = watched_paths.snapshot();
+            if let Some(new_stage) = changed_stage(&latest_snapshots, &new_snapshots) {
+                stage = stage.min(new_stage);
+                last_change_time = std::time::Instant::now();
+                latest_snapshots = new_snapshots;
+            }
+        }
+
+        let result = rebuild(
+            the_sims_directory,
+            source_directory,
+            object_name,
+            variant,
+            stage,
+            job_count,
+            options,
+        );
+        match result {
+            Ok(()) => message::info(&format!("Rebuilt {}", object_name)),
+            // a broken source should not stop the watch, the next change may fix it
+            Err(error) => message::rebuild_error(object_name, &error),
+        }
+
+        // the rebuild writes the xml file itself so start from the state it left behind
+        snapshots = watched_paths.snapshot();
+    }
+}
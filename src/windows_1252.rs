@@ -0,0 +1,44 @@
+use anyhow::Context;
+
+// windows-1252 matches latin-1 except for 0x80 to 0x9f
+// the 5 bytes that are undefined are mapped to the c1 control characters so they survive a round trip
+const CHARACTERS_0X80_TO_0X9F: [char; 32] = [
+    '\u{20ac}', '\u{0081}', '\u{201a}', '\u{0192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}', '\u{02c6}',
+    '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008d}', '\u{017d}', '\u{008f}', '\u{0090}', '\u{2018}',
+    '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}', '\u{02dc}', '\u{2122}', '\u{0161}',
+    '\u{203a}', '\u{0153}', '\u{009d}', '\u{017e}', '\u{0178}',
+];
+
+pub fn decode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|x| match x {
+            0x80..=0x9f => CHARACTERS_0X80_TO_0X9F[usize::from(x - 0x80)],
+            x => char::from(*x),
+        })
+        .collect()
+}
+
+fn encode_character(character: char) -> Option<u8> {
+    match u32::from(character) {
+        x @ (0x00..=0x7f | 0xa0..=0xff) => Some(u8::try_from(x).unwrap()),
+        _ => CHARACTERS_0X80_TO_0X9F
+            .iter()
+            .position(|x| *x == character)
+            .map(|x| 0x80 + u8::try_from(x).unwrap()),
+    }
+}
+
+pub fn encode(string: &str) -> anyhow::Result<Vec<u8>> {
+    string
+        .chars()
+        .map(|x| {
+            encode_character(x).with_context(|| {
+                format!(
+                    "\"{}\" contains the character '{}' which can not be represented in Windows-1252",
+                    string, x
+                )
+            })
+        })
+        .collect()
+}
@@ -1,6 +1,6 @@
-use crate::error;
 use crate::iff;
 use crate::iff_description;
+use crate::splitter;
 use crate::spr;
 use crate::sprite;
 
@@ -9,6 +9,8 @@ use anyhow::Context;
 fn build_sprite_description(
     source_directory: &std::path::Path,
     tile_directory: &std::path::Path,
+    chunk_id: iff::IffChunkId,
+    split_sprites: &sprite::SplitSprites,
 ) -> anyhow::Result<spr::Sprite> {
     let mut sprite_frames = Vec::new();
     let mut sprite_frame_index = 0;
@@ -23,10 +25,9 @@ fn build_sprite_description(
         for rotation in rotations {
             let sprite_description_file_path =
                 sprite::sprite_description_file_path(tile_directory, zoom_level, rotation);
-            if !sprite_description_file_path.is_file() {
+            let Some(sprite_description) = split_sprites.image_descriptions.get(&sprite_description_file_path) else {
                 continue;
-            }
-            let sprite_description = sprite::read_sprite_image_description_file(&sprite_description_file_path)?;
+            };
 
             let sprite_p_file_path =
                 sprite::sprite_channel_file_path(tile_directory, zoom_level, rotation, sprite::Channel::Color);
@@ -43,7 +44,7 @@ fn build_sprite_description(
                 sprite_frame_index,
                 zoom_level,
                 rotation,
-                &sprite_description,
+                sprite_description,
                 sprite_p_file_path,
                 sprite_z_file_path,
                 sprite_a_file_path,
@@ -53,15 +54,6 @@ fn build_sprite_description(
     }
     let chunk_label = tile_directory.file_name().unwrap().to_str().unwrap();
 
-    let chunk_id = {
-        let sprite_id_file_path = tile_directory.join("sprite id").with_extension("json");
-        let json_string = std::fs::read_to_string(&sprite_id_file_path)
-            .with_context(|| error::file_read_error(&sprite_id_file_path))?;
-
-        serde_json::from_str::<iff::IffChunkId>(&json_string)
-            .with_context(|| format!("Failed to deserialize json file {}", sprite_id_file_path.display()))?
-    };
-
     Ok(spr::Sprite::new(
         chunk_label,
         chunk_id,
@@ -70,28 +62,20 @@ fn build_sprite_description(
     ))
 }
 
-pub fn update(source_directory: &std::path::Path, object_name: &str, variant: Option<&str>) -> anyhow::Result<()> {
-    let xml_file_path = source_directory.join(object_name).with_extension("xml");
-
-    let mut iff_description = iff_description::IffDescription::open(&xml_file_path)
-        .with_context(|| format!("Failed to open xml file {}", xml_file_path.display()))?;
-
+/// Replaces the sprites of the description with the split sprites that have the same IDs and adds the new ones.
+pub fn replace_sprites(
+    iff_description: &mut iff_description::IffDescription,
+    source_directory: &std::path::Path,
+    split_sprites: &sprite::SplitSprites,
+) -> anyhow::Result<()> {
     let mut new_sprites = Vec::new();
-
-    let split_sprites_directory = {
-        let object_name = if let Some(variant) = variant {
-            format!("{} - {}", object_name, variant)
-        } else {
-            object_name.to_owned()
-        };
-        source_directory.join(format!("{} - sprites", object_name))
-    };
-    for entry in std::fs::read_dir(split_sprites_directory)? {
-        let path = entry?.path();
-        if !path.is_dir() {
-            continue;
-        }
-        new_sprites.push(build_sprite_description(source_directory, &path)?);
+    for (tile_directory, chunk_id) in &split_sprites.sprite_ids {
+        new_sprites.push(build_sprite_description(
+            source_directory,
+            tile_directory,
+            *chunk_id,
+            split_sprites,
+        )?);
     }
 
     let sprite_id_set: std::collections::HashSet<_> = new_sprites.iter().map(|x| x.chunk_id).collect();
@@ -99,6 +83,18 @@ pub fn update(source_directory: &std::path::Path, object_name: &str, variant: Op
     iff_description.sprites.sprites.retain(|x| !sprite_id_set.contains(&x.chunk_id));
     iff_description.sprites.sprites.append(&mut new_sprites);
     iff_description.sprites.sprites.sort_by(|a, b| a.chunk_id.as_i16().cmp(&b.chunk_id.as_i16()));
+    Ok(())
+}
+
+pub fn update(source_directory: &std::path::Path, object_name: &str, variant: Option<&str>) -> anyhow::Result<()> {
+    let xml_file_path = source_directory.join(object_name).with_extension("xml");
+
+    let mut iff_description = iff_description::IffDescription::open(&xml_file_path)
+        .with_context(|| format!("Failed to open xml file {}", xml_file_path.display()))?;
+
+    let split_sprites_directory = splitter::split_sprites_directory(source_directory, object_name, variant);
+    let split_sprites = sprite::SplitSprites::read_descriptions(&split_sprites_directory)?;
+    replace_sprites(&mut iff_description, source_directory, &split_sprites)?;
 
     iff_description
         .save(&xml_file_path)